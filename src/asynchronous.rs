@@ -0,0 +1,183 @@
+//! Searches awaited from async code
+//!
+//! The directory is walked by `FindOptions::run_with` on a background thread, so the blocking `std::fs` calls
+//! never run on the executor, and the paths found are handed to the future through a bounded queue. Nothing
+//! depends on a particular runtime: the thread wakes the future through the `Waker` it was last polled with.
+//! Dropping the future cancels the walk before its next entry
+
+use crate::{CancellationToken, Error, FindOptions, IntoControlFlow};
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+
+
+
+/// The number of paths the walk gets ahead of `process` by before it waits
+const QUEUE_SIZE: usize = 64;
+
+
+/// Run the search of `options`, awaiting `process` on every entry selected one after the other
+pub(crate) async fn run<R: IntoControlFlow, F: Future<Output = R>>(options: &FindOptions, mut process: impl FnMut(PathBuf) -> F) -> Result<usize, Error> {
+	let mut walk = Walk::start(options.clone());
+	let mut processed = 0;
+
+	loop {
+		match (Next{ walk: &walk }).await {
+			Message::Found(path) => {
+				processed += 1;
+
+				if process(path).await.into_control_flow().is_break() {
+					// Dropping the walk stops the thread
+					return Ok(processed);
+				}
+			},
+			Message::Done(Some(result)) => return result,
+			Message::Done(None) => walk.resume_panic(),
+		}
+	}
+}
+
+
+enum Message {
+	Found(PathBuf),
+	/// What the search returned, always the last message. `None` when the walk panicked
+	Done(Option<Result<usize, Error>>),
+}
+
+struct State {
+	queue: VecDeque<Message>,
+	/// The waker of the last poll that found the queue empty
+	waker: Option<Waker>,
+	/// Whether the future was dropped, so the walk has to stop
+	closed: bool,
+}
+
+struct Shared {
+	state: Mutex<State>,
+	/// Signaled when the queue has room again or is closed
+	room: Condvar,
+}
+
+impl Shared {
+	/// Queue `message` once there is room for it, `false` when the future is gone
+	fn send(&self, message: Message) -> bool {
+		let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+		while state.queue.len() >= QUEUE_SIZE && !state.closed {
+			state = self.room.wait(state).unwrap_or_else(|poisoned| poisoned.into_inner());
+		}
+
+		if state.closed {
+			return false;
+		}
+
+		state.queue.push_back(message);
+		let waker = state.waker.take();
+		drop(state);
+
+		if let Some(waker) = waker {
+			waker.wake();
+		}
+
+		true
+	}
+}
+
+
+/// Sends `Done` when dropped, so the future ends even when the walk panics
+struct Finish {
+	sender: Arc<Shared>,
+	result: Option<Result<usize, Error>>,
+}
+
+impl Drop for Finish {
+	fn drop(&mut self) {
+		self.sender.send(Message::Done(self.result.take()));
+	}
+}
+
+
+/// The receiving end of a walk running on its own thread, which is stopped when it is dropped
+struct Walk {
+	shared: Arc<Shared>,
+	/// Cancels the walk, along with the token of the options if they have one
+	cancellation: CancellationToken,
+	thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Walk {
+	fn start(mut options: FindOptions) -> Walk {
+		let shared = Arc::new(Shared{
+			state: Mutex::new(State{ queue: VecDeque::new(), waker: None, closed: false }),
+			room: Condvar::new(),
+		});
+
+		let cancellation = match &options.cancellation {
+			Some(token) => token.linked(),
+			None => CancellationToken::new(),
+		};
+		options.cancellation = Some(cancellation.clone());
+
+		let sender = shared.clone();
+		let thread = std::thread::spawn(move || {
+			let mut finish = Finish{ sender, result: None };
+			let result = options.run_with(|path| finish.sender.send(Message::Found(path.clone())));
+			finish.result = Some(result);
+		});
+
+		Walk{ shared, cancellation, thread: Some(thread) }
+	}
+
+	/// Propagate the panic of the walk, once it sent `Done(None)`
+	fn resume_panic(&mut self) -> ! {
+		match self.thread.take().map(|thread| thread.join()) {
+			Some(Err(panic)) => std::panic::resume_unwind(panic),
+			_ => unreachable!("the walk ended without a result but did not panic"),
+		}
+	}
+}
+
+impl Drop for Walk {
+	fn drop(&mut self) {
+		// Not joined, the thread stops before its next entry
+		self.cancellation.cancel();
+
+		let mut state = self.shared.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+		state.closed = true;
+		state.queue.clear();
+		drop(state);
+
+		self.shared.room.notify_all();
+	}
+}
+
+
+/// The next message of a walk
+struct Next<'a> {
+	walk: &'a Walk,
+}
+
+impl Future for Next<'_> {
+	type Output = Message;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Message> {
+		let mut state = self.walk.shared.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+		match state.queue.pop_front() {
+			Some(message) => {
+				drop(state);
+				self.walk.shared.room.notify_all();
+				Poll::Ready(message)
+			},
+			// Registered under the lock, so a message sent right after is never missed
+			None => {
+				state.waker = Some(cx.waker().clone());
+				Poll::Pending
+			},
+		}
+	}
+}
@@ -0,0 +1,263 @@
+//! Shell-style wildcard patterns
+//!
+//!   `*` - Any sequence of characters, including none
+//!   `?` - Exactly one character
+//!   `[abc]`, `[a-z]` - One character of the set or range
+//!   `[!abc]` - One character not in the set
+//!
+//! Any other character, including a metacharacter inside brackets (`[*]`), matches itself
+//!
+//! The matcher is not behind a cargo feature because the core of the search relies on it: the `exclude` patterns,
+//! the name patterns of `globs` (`find_by_glob_and_then`), ignore files and the directories of `find_in_glob_dirs`
+
+use crate::Error;
+
+use std::path::{Component, Path, PathBuf};
+
+
+
+/// A compiled wildcard pattern
+#[derive(Debug, Clone)]
+pub(crate) struct Pattern {
+	tokens: Vec<Token>,
+}
+
+#[derive(Debug, Clone)]
+enum Token {
+	Char(char),
+	AnyChar,
+	AnySequence,
+	Class {
+		negated: bool,
+		ranges: Vec<(char, char)>,
+	},
+}
+
+impl Token {
+	/// Whether this single character token matches `c`
+	fn matches(&self, c: char) -> bool {
+		match self {
+			Token::Char(expected) => *expected == c,
+			Token::AnyChar => true,
+			Token::AnySequence => false,
+			Token::Class { negated, ranges } => ranges.iter().any(|(lo, hi)| *lo <= c && c <= *hi) != *negated,
+		}
+	}
+}
+
+impl Pattern {
+	/// Compile `pattern`
+	///
+	/// # Errors
+	///   `InvalidPattern(String)` - A `[` is never closed
+	pub(crate) fn new(pattern: &str) -> Result<Pattern, Error> {
+		let mut tokens = Vec::new();
+		let mut chars = pattern.chars().peekable();
+
+		while let Some(c) = chars.next() {
+			match c {
+				'*' => {
+					// Consecutive stars are equivalent to a single one
+					if let Some(Token::AnySequence) = tokens.last() {
+						continue;
+					}
+					tokens.push(Token::AnySequence);
+				},
+				'?' => tokens.push(Token::AnyChar),
+				'[' => {
+					let negated = chars.peek() == Some(&'!');
+					if negated {
+						chars.next();
+					}
+
+					let mut ranges = Vec::new();
+					let mut closed = false;
+
+					// A `]` right after the opening bracket is part of the set
+					let mut first = true;
+					while let Some(c) = chars.next() {
+						if c == ']' && !first {
+							closed = true;
+							break;
+						}
+						first = false;
+
+						let mut lookahead = chars.clone();
+						match (lookahead.next(), lookahead.next()) {
+							(Some('-'), Some(hi)) if hi != ']' => {
+								chars.next();
+								chars.next();
+								ranges.push((c, hi));
+							},
+							_ => ranges.push((c, c)),
+						}
+					}
+
+					if !closed {
+						return Err(Error::InvalidPattern(format!("unclosed `[` in `{}`", pattern)));
+					}
+
+					tokens.push(Token::Class{ negated, ranges });
+				},
+				c => tokens.push(Token::Char(c)),
+			}
+		}
+
+		Ok(Pattern{ tokens })
+	}
+
+	/// Whether `text` matches the whole pattern
+	pub(crate) fn matches(&self, text: &str) -> bool {
+		let text: Vec<char> = text.chars().collect();
+
+		let (mut t, mut p) = (0, 0);
+		// Position of the last `*` seen and the text position it is currently matched up to
+		let mut backtrack: Option<(usize, usize)> = None;
+
+		while t < text.len() {
+			match self.tokens.get(p) {
+				Some(Token::AnySequence) => {
+					backtrack = Some((p, t));
+					p += 1;
+				},
+				Some(token) if token.matches(text[t]) => {
+					p += 1;
+					t += 1;
+				},
+				_ => match backtrack {
+					// Let the last star swallow one more character and retry
+					Some((star, matched)) => {
+						backtrack = Some((star, matched + 1));
+						p = star + 1;
+						t = matched + 1;
+					},
+					None => return false,
+				},
+			}
+		}
+
+		self.tokens[p..].iter().all(|token| matches!(token, Token::AnySequence))
+	}
+}
+
+
+/// The directories matching `pattern`, a path whose components can be wildcard patterns (`projects/*/assets`)
+///
+/// Each component is matched against the subdirectories of the directories matched so far, following symlinks.
+/// Components without wildcards are taken as they are, so `..` works. The directories are sorted by path,
+/// and a directory matched through several paths is only kept once
+///
+/// # Errors
+///   `InvalidPattern(String)` - A component could not be compiled
+///   `IoError(std::io::Error)` - A directory could not be listed, unless `ignore_fail` is set
+pub(crate) fn expand_dirs(pattern: &str, ignore_fail: bool) -> Result<Vec<PathBuf>, Error> {
+	let mut dirs = vec![PathBuf::new()];
+
+	for component in Path::new(pattern).components() {
+		let part = match component {
+			Component::Normal(part) => part.to_str().filter(|part| part.contains(['*', '?', '['])),
+			_ => None,
+		};
+
+		let part = match part {
+			Some(part) => Pattern::new(part)?,
+			None => {
+				dirs = dirs.iter().map(|dir| dir.join(component)).collect();
+				continue;
+			},
+		};
+
+		let mut matched = Vec::new();
+
+		for dir in &dirs {
+			// An empty path is the working directory, for relative patterns
+			let listed = if dir.as_os_str().is_empty() { Path::new(".") } else { dir.as_path() };
+
+			let entries = match std::fs::read_dir(listed) {
+				Ok(entries) => entries,
+				// Literal components may name directories that do not exist, or files
+				Err(err) if err.kind() == std::io::ErrorKind::NotFound || !listed.is_dir() => continue,
+				Err(_) if ignore_fail => continue,
+				Err(err) => return Err(Error::IoError(err)),
+			};
+
+			for entry in entries {
+				let entry = match entry {
+					Ok(entry) => entry,
+					Err(_) if ignore_fail => continue,
+					Err(err) => return Err(Error::IoError(err)),
+				};
+
+				let path = dir.join(entry.file_name());
+				if entry.file_name().to_str().is_some_and(|name| part.matches(name)) && path.is_dir() {
+					matched.push(path);
+				}
+			}
+		}
+
+		dirs = matched;
+	}
+
+	dirs.sort();
+
+	// `..` can lead several matches to the same directory, which is only kept once
+	let mut seen = std::collections::HashSet::new();
+	dirs.retain(|dir| dir.is_dir() && seen.insert(dir.canonicalize().unwrap_or_else(|_| dir.clone())));
+
+	Ok(dirs)
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::Pattern;
+	use crate::Error;
+
+	fn matches(pattern: &str, text: &str) -> bool {
+		Pattern::new(pattern).unwrap().matches(text)
+	}
+
+	#[test]
+	fn star_matches_any_sequence() {
+		assert!(matches("*.png", "a.png"));
+		assert!(matches("*.png", ".png"));
+		assert!(!matches("*.png", "a.png.bak"));
+		assert!(matches("a*b*c", "abc"));
+		assert!(matches("a*b*c", "a_b_b_c"));
+		assert!(!matches("a*b*c", "a_c_b"));
+		assert!(matches("**", ""));
+		assert!(matches("*", "é 😀"));
+	}
+
+	#[test]
+	fn question_mark_matches_one_character() {
+		assert!(matches("file?.txt", "file1.txt"));
+		assert!(matches("file?.txt", "fileé.txt"));
+		assert!(!matches("file?.txt", "file.txt"));
+		assert!(!matches("file?.txt", "file12.txt"));
+	}
+
+	#[test]
+	fn classes_match_one_character_of_the_set() {
+		assert!(matches("[abc].rs", "b.rs"));
+		assert!(!matches("[abc].rs", "d.rs"));
+		assert!(matches("v[0-9]", "v7"));
+		assert!(!matches("v[0-9]", "vx"));
+		assert!(matches("[!abc].rs", "d.rs"));
+		assert!(!matches("[!abc].rs", "a.rs"));
+		assert!(matches("[!0-9]*", "x12"));
+		assert!(!matches("[!0-9]*", "1x"));
+		// A `]` first is part of the set, and metacharacters inside brackets match themselves
+		assert!(matches("[]a]", "]"));
+		assert!(matches("[*?]", "*"));
+		assert!(!matches("[*?]", "x"));
+		assert!(matches("[a-]", "-"));
+	}
+
+	#[test]
+	fn unclosed_brackets_are_rejected() {
+		for pattern in ["[", "[abc", "a[!b", "[]", "*.[ch"] {
+			assert!(matches!(Pattern::new(pattern), Err(Error::InvalidPattern(_))), "{}", pattern);
+		}
+	}
+}
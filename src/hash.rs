@@ -0,0 +1,133 @@
+//! Content digests of files in the `HashAlgorithm`s enabled through cargo features
+
+#[cfg(feature = "md5")]
+mod md5;
+#[cfg(feature = "sha256")]
+mod sha256;
+
+use crate::{Error, HashAlgorithm};
+
+use std::io::Read;
+use std::path::Path;
+
+
+
+/// Size of the chunks files are read in, so a file is never held in memory as a whole
+const CHUNK_SIZE: usize = 64 * 1024;
+
+
+/// The running state of a digest
+trait Digest {
+	fn update(&mut self, data: &[u8]);
+
+	/// Finish the digest, returning its bytes
+	fn finish(self: Box<Self>) -> Vec<u8>;
+}
+
+#[cfg_attr(not(any(feature = "md5", feature = "sha256")), allow(unused_variables))]
+fn digest(algorithm: HashAlgorithm) -> Result<Box<dyn Digest>, Error> {
+	match algorithm {
+		#[cfg(feature = "md5")]
+		HashAlgorithm::MD5 => Ok(Box::new(md5::Md5::new())),
+		#[cfg(feature = "sha256")]
+		HashAlgorithm::SHA256 => Ok(Box::new(sha256::Sha256::new())),
+		#[allow(unreachable_patterns)]
+		_ => Err(Error::UnsupportedAlgorithm(algorithm)),
+	}
+}
+
+
+/// Check that `algorithm` is compiled in
+pub(crate) fn check(algorithm: HashAlgorithm) -> Result<(), Error> {
+	digest(algorithm).map(|_| ())
+}
+
+/// Read the file at `path` in chunks and return its lowercase hex digest
+pub(crate) fn digest_file(path: &Path, algorithm: HashAlgorithm) -> Result<String, Error> {
+	let mut digest = digest(algorithm)?;
+	let mut file = std::fs::File::open(path).map_err(Error::IoError)?;
+	let mut buffer = vec![0; CHUNK_SIZE];
+
+	loop {
+		match file.read(&mut buffer) {
+			Ok(0) => break,
+			Ok(n) => digest.update(&buffer[..n]),
+			Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+			Err(err) => return Err(Error::IoError(err)),
+		}
+	}
+
+	Ok(hex(digest))
+}
+
+/// Return the lowercase hex digest of `data`
+pub(crate) fn digest_bytes(data: &[u8], algorithm: HashAlgorithm) -> Result<String, Error> {
+	let mut digest = digest(algorithm)?;
+	digest.update(data);
+
+	Ok(hex(digest))
+}
+
+fn hex(digest: Box<dyn Digest>) -> String {
+	digest.finish().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+
+/// Splits the input of a Merkle-Damgard hash (MD5, SHA-256) into the 64 byte blocks it compresses
+#[cfg(any(feature = "md5", feature = "sha256"))]
+struct Blocks {
+	buffer: [u8; 64],
+	buffered: usize,
+	length: u64,
+}
+
+#[cfg(any(feature = "md5", feature = "sha256"))]
+impl Blocks {
+	fn new() -> Blocks {
+		Blocks{
+			buffer: [0; 64],
+			buffered: 0,
+			length: 0,
+		}
+	}
+
+	/// Hand every complete block of `data` to `compress`, keeping the rest for the next call
+	fn update(&mut self, mut data: &[u8], compress: &mut dyn FnMut(&[u8])) {
+		self.length = self.length.wrapping_add(data.len() as u64);
+
+		if self.buffered > 0 {
+			let take = (64 - self.buffered).min(data.len());
+			self.buffer[self.buffered..self.buffered + take].copy_from_slice(&data[..take]);
+			self.buffered += take;
+			data = &data[take..];
+
+			if self.buffered < 64 {
+				return;
+			}
+
+			compress(&self.buffer);
+			self.buffered = 0;
+		}
+
+		let mut blocks = data.chunks_exact(64);
+		for block in &mut blocks {
+			compress(block);
+		}
+
+		let rest = blocks.remainder();
+		self.buffer[..rest.len()].copy_from_slice(rest);
+		self.buffered = rest.len();
+	}
+
+	/// Pad the message and append its length in bits, little endian for MD5 and big endian for SHA-256
+	fn finish(&mut self, big_endian: bool, compress: &mut dyn FnMut(&[u8])) {
+		let bits = self.length.wrapping_mul(8);
+
+		// A `1` bit, then zeros up to 8 bytes before the end of a block
+		let mut padding = vec![0x80];
+		padding.resize(1 + (119 - self.buffered) % 64, 0);
+		padding.extend_from_slice(&if big_endian { bits.to_be_bytes() } else { bits.to_le_bytes() });
+
+		self.update(&padding, compress);
+	}
+}
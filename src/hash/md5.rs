@@ -0,0 +1,135 @@
+//! MD5 as specified in RFC 1321
+//!
+//! MD5 is broken for security purposes, but still fine to detect content changes
+
+use super::{Blocks, Digest};
+
+
+
+/// Per round shift amounts
+const S: [u32; 64] = [
+	7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22,
+	5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20,
+	4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23,
+	6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+/// Per round constants, the integer part of `abs(sin(i + 1)) * 2^32`
+const K: [u32; 64] = [
+	0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+	0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+	0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+	0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+	0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+	0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+	0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+	0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+
+pub(crate) struct Md5 {
+	state: [u32; 4],
+	blocks: Blocks,
+}
+
+impl Md5 {
+	pub(crate) fn new() -> Md5 {
+		Md5{
+			state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476],
+			blocks: Blocks::new(),
+		}
+	}
+}
+
+impl Digest for Md5 {
+	fn update(&mut self, data: &[u8]) {
+		let state = &mut self.state;
+		self.blocks.update(data, &mut |block| compress(state, block));
+	}
+
+	fn finish(mut self: Box<Self>) -> Vec<u8> {
+		let state = &mut self.state;
+		self.blocks.finish(false, &mut |block| compress(state, block));
+
+		self.state.iter().flat_map(|word| word.to_le_bytes()).collect()
+	}
+}
+
+
+fn compress(state: &mut [u32; 4], block: &[u8]) {
+	let mut m = [0u32; 16];
+	for (word, bytes) in m.iter_mut().zip(block.chunks_exact(4)) {
+		*word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+	}
+
+	let [mut a, mut b, mut c, mut d] = *state;
+
+	for i in 0..64 {
+		let (f, g) = match i / 16 {
+			0 => ((b & c) | (!b & d), i),
+			1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+			2 => (b ^ c ^ d, (3 * i + 5) % 16),
+			_ => (c ^ (b | !d), (7 * i) % 16),
+		};
+
+		let f = f.wrapping_add(a).wrapping_add(K[i]).wrapping_add(m[g]);
+		a = d;
+		d = c;
+		c = b;
+		b = b.wrapping_add(f.rotate_left(S[i]));
+	}
+
+	for (word, value) in state.iter_mut().zip([a, b, c, d]) {
+		*word = word.wrapping_add(value);
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::Md5;
+	use crate::hash::Digest;
+
+	/// The hex digest of `data`, handed over in chunks of `chunk` bytes
+	fn hex(data: &[u8], chunk: usize) -> String {
+		let mut digest = Box::new(Md5::new());
+		for part in data.chunks(chunk) {
+			digest.update(part);
+		}
+
+		digest.finish().iter().map(|b| format!("{:02x}", b)).collect()
+	}
+
+	#[test]
+	fn known_answers() {
+		assert_eq!(hex(b"", 1), "d41d8cd98f00b204e9800998ecf8427e");
+		assert_eq!(hex(b"abc", 1), "900150983cd24fb0d6963f7d28e17f72");
+		assert_eq!(hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq", 64), "8215ef0796a20bcaaae116d3876c664a");
+	}
+
+	#[test]
+	fn one_million_a() {
+		let data = vec![b'a'; 1_000_000];
+		assert_eq!(hex(&data, data.len()), "7707d6ae4e027c70eea2a935c2296f21");
+		// Chunks not aligned on blocks are buffered
+		assert_eq!(hex(&data, 7), "7707d6ae4e027c70eea2a935c2296f21");
+	}
+
+	#[test]
+	fn padding_boundaries() {
+		// The length fits after the message in its last block up to 55 bytes, and needs another block from 56
+		for (len, expected) in [
+			(55, "ef1772b6dff9a122358552954ad0df65"),
+			(56, "3b0c8ac703f828b04c6c197006d17218"),
+			(63, "b06521f39153d618550606be297466d5"),
+			(64, "014842d480b571495a4a0363793f7367"),
+			(65, "c743a45e0d2e6a95cb859adae0248435"),
+			(119, "8a7bd0732ed6a28ce75f6dabc90e1613"),
+			(120, "5f61c0ccad4cac44c75ff505e1f1e537"),
+		] {
+			let data = vec![b'a'; len];
+			assert_eq!(hex(&data, len), expected, "{} bytes", len);
+			assert_eq!(hex(&data, 1), expected, "{} bytes one at a time", len);
+		}
+	}
+}
@@ -0,0 +1,134 @@
+//! SHA-256 as specified in FIPS 180-4
+
+use super::{Blocks, Digest};
+
+
+
+/// Round constants, the first 32 bits of the fractional parts of the cube roots of the first 64 primes
+const K: [u32; 64] = [
+	0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+	0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+	0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+	0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+	0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+	0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+	0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+	0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+
+pub(crate) struct Sha256 {
+	state: [u32; 8],
+	blocks: Blocks,
+}
+
+impl Sha256 {
+	pub(crate) fn new() -> Sha256 {
+		Sha256{
+			state: [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19],
+			blocks: Blocks::new(),
+		}
+	}
+}
+
+impl Digest for Sha256 {
+	fn update(&mut self, data: &[u8]) {
+		let state = &mut self.state;
+		self.blocks.update(data, &mut |block| compress(state, block));
+	}
+
+	fn finish(mut self: Box<Self>) -> Vec<u8> {
+		let state = &mut self.state;
+		self.blocks.finish(true, &mut |block| compress(state, block));
+
+		self.state.iter().flat_map(|word| word.to_be_bytes()).collect()
+	}
+}
+
+
+fn compress(state: &mut [u32; 8], block: &[u8]) {
+	let mut w = [0u32; 64];
+	for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+		*word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+	}
+
+	for i in 16..64 {
+		let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+		let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+		w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+	}
+
+	let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+
+	for i in 0..64 {
+		let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+		let ch = (e & f) ^ (!e & g);
+		let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(K[i]).wrapping_add(w[i]);
+		let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+		let maj = (a & b) ^ (a & c) ^ (b & c);
+		let t2 = s0.wrapping_add(maj);
+
+		h = g;
+		g = f;
+		f = e;
+		e = d.wrapping_add(t1);
+		d = c;
+		c = b;
+		b = a;
+		a = t1.wrapping_add(t2);
+	}
+
+	for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+		*word = word.wrapping_add(value);
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::Sha256;
+	use crate::hash::Digest;
+
+	/// The hex digest of `data`, handed over in chunks of `chunk` bytes
+	fn hex(data: &[u8], chunk: usize) -> String {
+		let mut digest = Box::new(Sha256::new());
+		for part in data.chunks(chunk) {
+			digest.update(part);
+		}
+
+		digest.finish().iter().map(|b| format!("{:02x}", b)).collect()
+	}
+
+	#[test]
+	fn known_answers() {
+		assert_eq!(hex(b"", 1), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+		assert_eq!(hex(b"abc", 1), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+		assert_eq!(hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq", 64), "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+	}
+
+	#[test]
+	fn one_million_a() {
+		let data = vec![b'a'; 1_000_000];
+		assert_eq!(hex(&data, data.len()), "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0");
+		// Chunks not aligned on blocks are buffered
+		assert_eq!(hex(&data, 7), "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0");
+	}
+
+	#[test]
+	fn padding_boundaries() {
+		// The length fits after the message in its last block up to 55 bytes, and needs another block from 56
+		for (len, expected) in [
+			(55, "9f4390f8d30c2dd92ec9f095b65e2b9ae9b0a925a5258e241c9f1e910f734318"),
+			(56, "b35439a4ac6f0948b6d6f9e3c6af0f5f590ce20f1bde7090ef7970686ec6738a"),
+			(63, "7d3e74a05d7db15bce4ad9ec0658ea98e3f06eeecf16b4c6fff2da457ddc2f34"),
+			(64, "ffe054fe7ae0cb6dc65c3af9b61d5209f439851db43d0ba5997337df154668eb"),
+			(65, "635361c48bb9eab14198e76ea8ab7f1a41685d6ad62aa9146d301d4f17eb0ae0"),
+			(119, "31eba51c313a5c08226adf18d4a359cfdfd8d2e816b13f4af952f7ea6584dcfb"),
+			(120, "2f3d335432c70b580af0e8e1b3674a7c020d683aa5f73aaaedfdc55af904c21c"),
+		] {
+			let data = vec![b'a'; len];
+			assert_eq!(hex(&data, len), expected, "{} bytes", len);
+			assert_eq!(hex(&data, 1), expected, "{} bytes one at a time", len);
+		}
+	}
+}
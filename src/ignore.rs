@@ -0,0 +1,248 @@
+//! Parsing and matching of `.gitignore` style ignore files
+
+use crate::glob::Pattern;
+use crate::Error;
+
+use std::path::{Path, PathBuf};
+
+
+
+/// A single component of an ignore rule
+#[derive(Debug, Clone)]
+enum Segment {
+	/// `**`, any number of path components
+	AnyComponents,
+	Glob(Pattern),
+}
+
+/// A line of an ignore file
+#[derive(Debug, Clone)]
+struct Rule {
+	segments: Vec<Segment>,
+	negated: bool,
+	dir_only: bool,
+}
+
+impl Rule {
+	/// Parse a line, returning `None` for blank lines and comments
+	fn parse(line: &str) -> Option<Rule> {
+		let mut line = line;
+
+		// Trailing spaces are ignored unless escaped with a backslash
+		while line.ends_with(' ') && !line.ends_with("\\ ") {
+			line = &line[..line.len() - 1];
+		}
+
+		if line.is_empty() || line.starts_with('#') {
+			return None;
+		}
+
+		// A leading backslash escapes a literal `!` or `#`
+		let negated = line.starts_with('!');
+		if negated || line.starts_with("\\!") || line.starts_with("\\#") {
+			line = &line[1..];
+		}
+
+		let dir_only = line.ends_with('/');
+		let line = line.trim_end_matches('/');
+
+		// A slash anywhere but at the end anchors the rule to the directory of the ignore file
+		let anchored = line.contains('/');
+		let line = line.trim_start_matches('/');
+
+		if line.is_empty() {
+			return None;
+		}
+
+		let mut segments = Vec::new();
+		if !anchored {
+			segments.push(Segment::AnyComponents);
+		}
+
+		for component in line.split('/').filter(|c| !c.is_empty()) {
+			if component == "**" {
+				segments.push(Segment::AnyComponents);
+			} else {
+				// An invalid pattern can never match anything, like git does with it
+				segments.push(Segment::Glob(Pattern::new(&component.replace("\\ ", " ")).ok()?));
+			}
+		}
+
+		Some(Rule{ segments, negated, dir_only })
+	}
+
+	fn matches(&self, components: &[&str], is_dir: bool) -> bool {
+		(is_dir || !self.dir_only) && match_segments(&self.segments, components)
+	}
+}
+
+fn match_segments(segments: &[Segment], components: &[&str]) -> bool {
+	match segments.split_first() {
+		None => components.is_empty(),
+		Some((Segment::AnyComponents, rest)) => {
+			// A trailing `**` matches everything inside, but not the directory itself
+			let min = if rest.is_empty() { 1 } else { 0 };
+			(min..=components.len()).any(|skip| match_segments(rest, &components[skip..]))
+		},
+		Some((Segment::Glob(pattern), rest)) => match components.split_first() {
+			Some((first, others)) => pattern.matches(first) && match_segments(rest, others),
+			None => false,
+		},
+	}
+}
+
+
+/// The rules of one ignore file, relative to the directory the file applies to
+#[derive(Debug, Clone)]
+pub(crate) struct IgnoreList {
+	base: PathBuf,
+	rules: Vec<Rule>,
+}
+
+impl IgnoreList {
+	pub(crate) fn parse(base: &Path, text: &str) -> IgnoreList {
+		IgnoreList{
+			base: base.to_path_buf(),
+			rules: text.lines().filter_map(Rule::parse).collect(),
+		}
+	}
+
+	/// Read the ignore file at `path`, applying it to `base`
+	///
+	/// A missing file results in `None`
+	pub(crate) fn read(base: &Path, path: &Path) -> Result<Option<IgnoreList>, Error> {
+		match std::fs::read(path) {
+			Ok(bytes) => Ok(Some(IgnoreList::parse(base, &String::from_utf8_lossy(&bytes)))),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+			Err(e) => Err(Error::IoError(e)),
+		}
+	}
+
+	/// Whether the last rule matching `path` ignores (`Some(true)`) or re-includes (`Some(false)`) it
+	fn decide(&self, path: &Path, is_dir: bool) -> Option<bool> {
+		let relative = path.strip_prefix(&self.base).ok()?;
+		let components: Vec<&str> = relative.iter().map(|c| c.to_str()).collect::<Option<_>>()?;
+
+		self.rules.iter().rev().find(|rule| rule.matches(&components, is_dir)).map(|rule| !rule.negated)
+	}
+}
+
+
+/// Whether `path` is ignored by `lists`, ordered from the lowest to the highest precedence
+pub(crate) fn is_ignored(lists: &[IgnoreList], path: &Path, is_dir: bool) -> bool {
+	lists.iter().rev().find_map(|list| list.decide(path, is_dir)).unwrap_or(false)
+}
+
+
+/// Path of the user's global git excludes file
+///
+/// This is `core.excludesFile` from `~/.gitconfig` when it is set, `$XDG_CONFIG_HOME/git/ignore`
+/// or `~/.config/git/ignore` otherwise
+pub(crate) fn global_excludes_path() -> Option<PathBuf> {
+	let home = std::env::var_os("HOME").map(PathBuf::from);
+
+	if let Some(home) = &home {
+		if let Ok(config) = std::fs::read_to_string(home.join(".gitconfig")) {
+			let mut in_core = false;
+
+			for line in config.lines().map(str::trim) {
+				if line.starts_with('[') {
+					in_core = line.trim_start_matches('[').trim_end_matches(']').trim().eq_ignore_ascii_case("core");
+				} else if in_core {
+					let mut parts = line.splitn(2, '=');
+					let key = parts.next().unwrap_or("").trim();
+					let value = parts.next().unwrap_or("").trim().trim_matches('"');
+
+					if key.eq_ignore_ascii_case("excludesfile") && !value.is_empty() {
+						return Some(match value.strip_prefix("~/") {
+							Some(rest) => home.join(rest),
+							None => PathBuf::from(value),
+						});
+					}
+				}
+			}
+		}
+	}
+
+	match std::env::var_os("XDG_CONFIG_HOME") {
+		Some(config) if !config.is_empty() => Some(PathBuf::from(config).join("git").join("ignore")),
+		_ => home.map(|home| home.join(".config").join("git").join("ignore")),
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::{is_ignored, IgnoreList};
+
+	use std::path::Path;
+
+	fn ignored(lists: &[IgnoreList], path: &str, is_dir: bool) -> bool {
+		is_ignored(lists, &Path::new("/repo").join(path), is_dir)
+	}
+
+	fn list(text: &str) -> IgnoreList {
+		IgnoreList::parse(Path::new("/repo"), text)
+	}
+
+	#[test]
+	fn negation_re_includes_and_the_last_rule_wins() {
+		let lists = [list("*.log\n!keep.log\n")];
+		assert!(ignored(&lists, "a.log", false));
+		assert!(ignored(&lists, "sub/a.log", false));
+		assert!(!ignored(&lists, "keep.log", false));
+		assert!(!ignored(&lists, "sub/keep.log", false));
+
+		let lists = [list("!keep.log\n*.log\n")];
+		assert!(ignored(&lists, "keep.log", false));
+
+		// A literal `!` is escaped
+		let lists = [list("\\!important\n")];
+		assert!(ignored(&lists, "!important", false));
+	}
+
+	#[test]
+	fn deeper_lists_take_precedence() {
+		let lists = [list("*.log\n"), IgnoreList::parse(Path::new("/repo/sub"), "!*.log\n")];
+		assert!(ignored(&lists, "a.log", false));
+		assert!(!ignored(&lists, "sub/a.log", false));
+	}
+
+	#[test]
+	fn trailing_slash_only_matches_directories() {
+		let lists = [list("build/\n")];
+		assert!(ignored(&lists, "build", true));
+		assert!(ignored(&lists, "src/build", true));
+		assert!(!ignored(&lists, "build", false));
+	}
+
+	#[test]
+	fn slashes_anchor_rules_to_the_ignore_file() {
+		let lists = [list("/target\ndocs/*.html\n")];
+		assert!(ignored(&lists, "target", true));
+		assert!(!ignored(&lists, "sub/target", true));
+		assert!(ignored(&lists, "docs/index.html", false));
+		assert!(!ignored(&lists, "sub/docs/index.html", false));
+
+		let lists = [list("target\n")];
+		assert!(ignored(&lists, "sub/target", true));
+	}
+
+	#[test]
+	fn double_stars_match_any_depth() {
+		let lists = [list("**/cache\nlogs/**\na/**/b\n")];
+		assert!(ignored(&lists, "cache", true));
+		assert!(ignored(&lists, "x/y/cache", true));
+		assert!(ignored(&lists, "logs/a/b.txt", false));
+		assert!(!ignored(&lists, "logs", true));
+		assert!(ignored(&lists, "a/b", false));
+		assert!(ignored(&lists, "a/x/y/b", false));
+	}
+
+	#[test]
+	fn comments_and_blank_lines_are_skipped() {
+		let lists = [list("# *.rs\n\n   \n\\#notes\n")];
+		assert!(!ignored(&lists, "main.rs", false));
+		assert!(ignored(&lists, "#notes", false));
+	}
+}
@@ -0,0 +1,219 @@
+//! Change notifications of the Linux kernel (`inotify`), waking a watcher only when a watched directory changes
+//!
+//! Only the flag and error values that are the same on every Linux architecture are used: the descriptors
+//! are blocking, and only read once `poll` reported them readable
+
+use crate::Error;
+
+use std::ffi::CString;
+use std::os::raw::{c_int, c_short, c_ulong, c_void};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+
+
+const F_SETFD: c_int = 2;
+const FD_CLOEXEC: c_int = 1;
+
+const IN_MODIFY: u32 = 0x2;
+const IN_ATTRIB: u32 = 0x4;
+const IN_CLOSE_WRITE: u32 = 0x8;
+const IN_MOVED_FROM: u32 = 0x40;
+const IN_MOVED_TO: u32 = 0x80;
+const IN_CREATE: u32 = 0x100;
+const IN_DELETE: u32 = 0x200;
+const IN_DELETE_SELF: u32 = 0x400;
+const IN_MOVE_SELF: u32 = 0x800;
+
+/// Everything that can change the entries of a directory or their modification dates
+const WATCH_MASK: u32 = IN_MODIFY | IN_ATTRIB | IN_CLOSE_WRITE | IN_MOVED_FROM | IN_MOVED_TO | IN_CREATE | IN_DELETE | IN_DELETE_SELF | IN_MOVE_SELF;
+
+/// How long a burst of changes has to have stopped for before it is reported, so a file being written is
+/// searched once rather than once per write
+const SETTLE_MS: c_int = 50;
+
+/// The longest changes are waited for to settle, so a file written without pause is still searched regularly
+const MAX_SETTLE: Duration = Duration::from_secs(1);
+
+const POLLIN: c_short = 1;
+const EINTR: i32 = 4;
+
+#[repr(C)]
+struct PollFd {
+	fd: c_int,
+	events: c_short,
+	revents: c_short,
+}
+
+extern "C" {
+	fn inotify_init1(flags: c_int) -> c_int;
+	fn inotify_add_watch(fd: c_int, pathname: *const std::os::raw::c_char, mask: u32) -> c_int;
+	fn eventfd(initval: u32, flags: c_int) -> c_int;
+	fn fcntl(fd: c_int, cmd: c_int, ...) -> c_int;
+	fn poll(fds: *mut PollFd, nfds: c_ulong, timeout: c_int) -> c_int;
+	fn read(fd: c_int, buf: *mut c_void, count: usize) -> isize;
+	fn write(fd: c_int, buf: *const c_void, count: usize) -> isize;
+	fn close(fd: c_int) -> c_int;
+}
+
+
+/// A file descriptor closed when dropped
+#[derive(Debug)]
+struct Fd(c_int);
+
+impl Fd {
+	/// Own `fd`, closed when a child process is executed
+	fn new(fd: c_int) -> Result<Fd, Error> {
+		if fd < 0 {
+			return Err(Error::IoError(std::io::Error::last_os_error()));
+		}
+
+		let fd = Fd(fd);
+
+		// SAFETY: `fd` is an open descriptor, and `F_SETFD` takes an integer
+		if unsafe { fcntl(fd.0, F_SETFD, FD_CLOEXEC) } < 0 {
+			return Err(Error::IoError(std::io::Error::last_os_error()));
+		}
+
+		Ok(fd)
+	}
+
+	/// Read what is pending, which `poll` reported there is, so the read does not block. The events of
+	/// `inotify` that do not fit in one read are left, and make the next `poll` return right away
+	fn consume(&self) -> Result<(), Error> {
+		let mut buffer = [0u8; 4096];
+
+		loop {
+			// SAFETY: `buffer` is writable for its whole length
+			let read = unsafe { read(self.0, buffer.as_mut_ptr() as *mut c_void, buffer.len()) };
+
+			if read >= 0 {
+				return Ok(());
+			}
+
+			let err = std::io::Error::last_os_error();
+			if err.raw_os_error() != Some(EINTR) {
+				return Err(Error::IoError(err));
+			}
+		}
+	}
+}
+
+impl Drop for Fd {
+	fn drop(&mut self) {
+		// SAFETY: the descriptor is owned by `self` alone
+		unsafe { close(self.0) };
+	}
+}
+
+
+/// Wakes the thread waiting in `Inotify::wait` from another thread
+#[derive(Debug)]
+pub(crate) struct Wake(Fd);
+
+impl Wake {
+	pub(crate) fn wake(&self) {
+		let one: u64 = 1;
+		// SAFETY: an eventfd is written 8 bytes at a time. It only fails when the counter would overflow,
+		// and then a wake up is pending anyway
+		unsafe { write((self.0).0, &one as *const u64 as *const c_void, 8) };
+	}
+}
+
+
+/// The `inotify` instance of a watcher
+#[derive(Debug)]
+pub(crate) struct Inotify {
+	fd: Fd,
+	wake: std::sync::Arc<Wake>,
+}
+
+impl Inotify {
+	/// A new instance, along with what wakes its `wait` up
+	pub(crate) fn new() -> Result<(Inotify, std::sync::Arc<Wake>), Error> {
+		// SAFETY: plain system calls, checked for failure by `Fd::new`
+		let fd = Fd::new(unsafe { inotify_init1(0) })?;
+		let wake = std::sync::Arc::new(Wake(Fd::new(unsafe { eventfd(0, 0) })?));
+
+		Ok((Inotify{ fd, wake: wake.clone() }, wake))
+	}
+
+	/// Watch the entries of `directory`. Watching a directory again is harmless
+	pub(crate) fn watch(&self, directory: &Path) -> Result<(), Error> {
+		let path = match CString::new(directory.as_os_str().as_bytes()) {
+			Ok(path) => path,
+			Err(_) => return Err(Error::NullDirectory),
+		};
+
+		// SAFETY: `path` is a NUL terminated string living until the call returns
+		if unsafe { inotify_add_watch((self.fd).0, path.as_ptr(), WATCH_MASK) } < 0 {
+			return Err(Error::IoError(std::io::Error::last_os_error()));
+		}
+
+		Ok(())
+	}
+
+	/// Wait until a watched directory changed and no other change followed for `SETTLE_MS`, or `MAX_SETTLE`
+	/// passed since the first change, returning `true`, or until woken up by `Wake`, returning `false`.
+	/// The changes and the wake up are consumed
+	pub(crate) fn wait(&self) -> Result<bool, Error> {
+		let mut first_change: Option<Instant> = None;
+
+		loop {
+			let timeout = match first_change {
+				None => -1,
+				Some(first) => match MAX_SETTLE.checked_sub(first.elapsed()) {
+					Some(left) => SETTLE_MS.min(left.as_millis() as c_int),
+					None => return Ok(true),
+				},
+			};
+
+			match self.poll(timeout)? {
+				Ready::Changed => {
+					self.fd.consume()?;
+					first_change.get_or_insert_with(Instant::now);
+				},
+				Ready::Woken => {
+					// Resets the counter of the eventfd, which would stay readable otherwise
+					(self.wake.0).consume()?;
+					return Ok(false);
+				},
+				Ready::TimedOut => return Ok(true),
+			}
+		}
+	}
+
+	/// Wait for at most `timeout` milliseconds, or forever when it is negative
+	fn poll(&self, timeout: c_int) -> Result<Ready, Error> {
+		let mut fds = [
+			PollFd{ fd: (self.fd).0, events: POLLIN, revents: 0 },
+			PollFd{ fd: (self.wake.0).0, events: POLLIN, revents: 0 },
+		];
+
+		loop {
+			// SAFETY: `fds` holds two initialized entries
+			let ready = unsafe { poll(fds.as_mut_ptr(), 2, timeout) };
+
+			if ready == 0 {
+				return Ok(Ready::TimedOut);
+			}
+
+			if ready > 0 {
+				return Ok(if fds[1].revents != 0 { Ready::Woken } else { Ready::Changed });
+			}
+
+			let err = std::io::Error::last_os_error();
+			if err.raw_os_error() != Some(EINTR) {
+				return Err(Error::IoError(err));
+			}
+		}
+	}
+}
+
+
+enum Ready {
+	Changed,
+	Woken,
+	TimedOut,
+}
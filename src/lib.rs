@@ -2,208 +2,3261 @@
 //! Assortment of functions to process and check
 //! necessary files at runtime
 
-// External crates
-#[cfg(feature = "json")]
-extern crate serde;
-#[cfg(feature = "json")]
-extern crate serde_derive;
-#[cfg(feature = "json")]
-extern crate serde_json;
+#[cfg(feature = "async")]
+mod asynchronous;
+mod glob;
+mod hash;
+#[cfg(feature = "ignore")]
+mod ignore;
+#[cfg(all(target_os = "linux", feature = "watch"))]
+mod inotify;
+#[cfg(feature = "mime")]
+mod mime;
+#[cfg(all(unix, feature = "memmap"))]
+mod mmap;
+#[cfg(feature = "regex")]
+mod regex;
+mod search;
+mod snapshot;
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::num::NonZeroUsize;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(all(unix, feature = "memmap"))]
+pub use mmap::Mmap;
+
+
+
+/// Check that `directory` can be searched
+fn check_directory(directory: &Path) -> Result<(), Error> {
+	match directory.to_str() {
+		Some(_) => match std::fs::metadata(directory) {
+			Ok(metadata) if metadata.is_dir() => Ok(()),
+			Ok(_) => Err(Error::NotADirectory(directory.to_path_buf())),
+			Err(_) => Err(Error::DirectoryDoesNotExist(directory.to_path_buf())),
+		},
+		None => Err(Error::NullDirectory),
+	}
+}
+
+
+/// The configuration of a search, built with chainable setters
+/// 
+/// A search walks `directory` and processes every entry matching all of the criteria that were set
+/// (`filenames`, `extensions`, `globs`, `regex`, `size`, `age`, `magic`, see `match_any` to only need one of the first four),
+/// or every entry when none was set.
+/// The other setters change how the directory is walked. The `find_*` functions are shorthands for
+/// common configurations:
+/// 
+/// ```
+/// use file_processor::{FindOptions, SortOrder, SymlinkPolicy};
+/// 
+/// let processed = FindOptions::new(".")
+///     .extensions(vec![String::from("rs")])
+///     .recursive(true)
+///     .include_hidden(false)
+///     .symlinks(SymlinkPolicy::Skip)
+///     .sort(Some(SortOrder::NameAsc))
+///     .run_with(|path| println!("{}", path.display()));
+/// ```
+/// 
+/// # Defaults
+///   `match_any` - `false`
+///   `ignore_fail` - `false`
+///   `fail_fast` - `false`
+///   `recursive` - `false`
+///   `max_depth` - `None`
+///   `case_insensitive` - `false`
+///   `relative_paths` - `false`
+///   `canonicalize` - `false`
+///   `relative` - `false`
+///   `lossy_names` - `false`
+///   `symlinks` - `SymlinkPolicy::Follow`
+///   `include_hidden` - `true`
+///   `sort` - `None`
+///   `cancellation` - Not cancellable
+///   `timeout` - `None`
+///   `trace` - No hook
+///   `dry_run` - `false`
+///   `ignore_files` - Not honored (`ignore` feature)
+///   `entry_kind` - `EntryKind::Both`
+#[derive(Debug, Clone)]
+pub struct FindOptions {
+	pub(crate) directory: PathBuf,
+	pub(crate) filenames: Option<Vec<String>>,
+	pub(crate) extensions: Option<Vec<String>>,
+	pub(crate) globs: Option<Vec<String>>,
+	pub(crate) exclude: Option<Vec<String>>,
+	#[cfg(feature = "regex")]
+	pub(crate) regex: Option<String>,
+	pub(crate) size: (Option<u64>, Option<u64>),
+	pub(crate) age: (Option<Duration>, Option<Duration>),
+	pub(crate) magic: Option<Vec<u8>>,
+	pub(crate) match_any: bool,
+	#[cfg(feature = "ignore")]
+	pub(crate) ignore_files: Option<IgnoreFiles>,
+	pub(crate) entry_kind: EntryKind,
+	pub(crate) ignore_fail: bool,
+	pub(crate) fail_fast: bool,
+	pub(crate) recursive: bool,
+	pub(crate) max_depth: Option<usize>,
+	pub(crate) case_insensitive: bool,
+	pub(crate) relative_paths: bool,
+	pub(crate) canonicalize: bool,
+	pub(crate) relative: bool,
+	pub(crate) lossy_names: bool,
+	pub(crate) symlinks: SymlinkPolicy,
+	pub(crate) include_hidden: bool,
+	pub(crate) sort: Option<SortOrder>,
+	pub(crate) cancellation: Option<CancellationToken>,
+	pub(crate) timeout: Option<Duration>,
+	pub(crate) trace: Option<fn(&TraceEvent)>,
+	pub(crate) dry_run: bool,
+}
+
+impl FindOptions {
+	/// A search of `directory` (anything that is `AsRef<Path>`, like `&str` or `PathBuf`) with the default options
+	pub fn new(directory: impl AsRef<Path>) -> FindOptions {
+		FindOptions{
+			directory: directory.as_ref().to_path_buf(),
+			filenames: None,
+			extensions: None,
+			globs: None,
+			exclude: None,
+			#[cfg(feature = "regex")]
+			regex: None,
+			size: (None, None),
+			age: (None, None),
+			magic: None,
+			match_any: false,
+			#[cfg(feature = "ignore")]
+			ignore_files: None,
+			entry_kind: EntryKind::Both,
+			ignore_fail: false,
+			fail_fast: false,
+			recursive: false,
+			max_depth: None,
+			case_insensitive: false,
+			relative_paths: false,
+			canonicalize: false,
+			relative: false,
+			lossy_names: false,
+			symlinks: SymlinkPolicy::Follow,
+			include_hidden: true,
+			sort: None,
+			cancellation: None,
+			timeout: None,
+			trace: None,
+			dry_run: false,
+		}
+	}
+
+	/// Resolve a relative `directory` against `base`, so the search does not depend on the working directory
+	/// 
+	/// The working directory differs between `cargo run` and a deployed binary, while a base given by the program
+	/// does not. For assets shipped with a crate, the base can be its manifest directory:
+	/// 
+	/// ```
+	/// use file_processor::FindOptions;
+	/// 
+	/// let assets = FindOptions::new("assets")
+	///     .resolve_base(option_env!("CARGO_MANIFEST_DIR").map(Into::into))
+	///     .extensions(vec![String::from("png")]);
+	/// ```
+	/// 
+	/// An absolute `directory` and a `None` base are kept as is. The paths given to `process` start with `base`
+	pub fn resolve_base(mut self, base: Option<PathBuf>) -> FindOptions {
+		if let Some(base) = base {
+			if self.directory.is_relative() {
+				self.directory = base.join(&self.directory);
+			}
+		}
+		self
+	}
+
+	/// Only process entries named like one of `filenames`
+	/// 
+	/// The search fails with `MissingFiles` when some of them are not found. Duplicates are allowed and looked up
+	/// once, like names only differing in ASCII case with `case_insensitive`: each matching entry is processed
+	/// once, and a missing name is reported once, in the order it was first requested
+	pub fn filenames(mut self, filenames: Vec<String>) -> FindOptions {
+		self.filenames = Some(filenames);
+		self
+	}
+
+	/// Only process entries with one of the `extensions`, compared ignoring ASCII case (`jpg` matches `photo.JPG`)
+	/// 
+	/// Extensions are given without their dot (`jpg`), a single leading `.` (`.jpg`) is stripped. They are compared
+	/// with the end of the name, so compound extensions work too: `archive.tar.gz` has the extensions `gz` and `tar.gz`,
+	/// but `backup.gz` only has `gz`. A name has to start with something else than its extension (`.gz` has none)
+	pub fn extensions(mut self, extensions: Vec<String>) -> FindOptions {
+		self.extensions = Some(extensions);
+		self
+	}
+
+	/// Only process entries whose name matches one of the wildcard `patterns` (see `find_by_glob_and_then`)
+	pub fn globs(mut self, patterns: Vec<String>) -> FindOptions {
+		self.globs = Some(patterns);
+		self
+	}
+
+	/// Never process entries whose name matches one of the wildcard `patterns`, whatever they match otherwise
+	/// 
+	/// This selects every `.rs` file except `build.rs` and the generated ones with
+	/// `.extensions(vec!["rs".into()]).exclude(vec!["build.rs".into(), "*_generated.rs".into()])`.
+	/// Excluded directories are still walked with `recursive`, see `ignore_files` (`ignore` feature)
+	/// to leave out whole subtrees.
+	/// A requested filename that is excluded is never found
+	pub fn exclude(mut self, patterns: Vec<String>) -> FindOptions {
+		self.exclude = Some(patterns);
+		self
+	}
+
+	/// Only process entries whose name matches the regular expression `pattern` (see `find_by_regex_and_then`)
+	/// 
+	/// Requires the `regex` feature
+	#[cfg(feature = "regex")]
+	pub fn regex(mut self, pattern: &str) -> FindOptions {
+		self.regex = Some(pattern.to_string());
+		self
+	}
+
+	/// Only process files whose size in bytes falls in the inclusive range `[min, max]` (see `find_by_size_and_then`)
+	pub fn size(mut self, min: Option<u64>, max: Option<u64>) -> FindOptions {
+		self.size = (min, max);
+		self
+	}
+
+	/// Only process files last modified more than `older_than` and less than `newer_than` ago (see `find_by_age_and_then`)
+	pub fn age(mut self, older_than: Option<Duration>, newer_than: Option<Duration>) -> FindOptions {
+		self.age = (older_than, newer_than);
+		self
+	}
+
+	/// Only process files whose contents start with the bytes `magic` (see `find_by_magic_and_then`)
+	pub fn magic(mut self, magic: &[u8]) -> FindOptions {
+		self.magic = Some(magic.to_vec());
+		self
+	}
+
+	/// Whether matching any one of the name criteria (`filenames`, `extensions`, `globs`, `regex`) is enough,
+	/// instead of all of them
+	/// 
+	/// This selects `config.toml` plus every `*.yaml` file in a single pass. `size` and `age` still have to match.
+	/// A requested filename is only found by an entry with that name
+	pub fn match_any(mut self, match_any: bool) -> FindOptions {
+		self.match_any = match_any;
+		self
+	}
+
+	/// Skip the entries excluded by the given ignore files (see `find_respecting_ignore`)
+	/// 
+	/// Requires the `ignore` feature
+	#[cfg(feature = "ignore")]
+	pub fn ignore_files(mut self, ignore_files: IgnoreFiles) -> FindOptions {
+		self.ignore_files = Some(ignore_files);
+		self
+	}
+
+	/// Which kinds of entries `process` is called on
+	/// 
+	/// Directories that are not processed are still descended into when `recursive` is set
+	pub fn entry_kind(mut self, entry_kind: EntryKind) -> FindOptions {
+		self.entry_kind = entry_kind;
+		self
+	}
+
+	/// Whether incorrect or corrupt paths are skipped instead of returning a `file_processor::Error`
+	/// 
+	/// This covers entries that could not be examined and subdirectories that could not be read, which are left
+	/// out along with everything below them. The searched directory itself is always read, and an error is
+	/// returned when it cannot be
+	pub fn ignore_fail(mut self, ignore_fail: bool) -> FindOptions {
+		self.ignore_fail = ignore_fail;
+		self
+	}
+
+	/// Whether the search stops as soon as every requested filename has been found, and reports only the first
+	/// missing name otherwise
+	/// 
+	/// This is for strict checks that every file is present (`require_files`) on large directories. A name is
+	/// only confirmed missing once the whole directory has been walked, but the walk no longer goes on once
+	/// nothing is left to find, so a name present in several directories is only found once. Without
+	/// `filenames` it has no effect
+	pub fn fail_fast(mut self, fail_fast: bool) -> FindOptions {
+		self.fail_fast = fail_fast;
+		self
+	}
+
+	/// Whether subdirectories are searched too (depth-first)
+	/// 
+	/// Symlinked directories are followed, but every directory is only walked once
+	pub fn recursive(mut self, recursive: bool) -> FindOptions {
+		self.recursive = recursive;
+		self
+	}
+
+	/// How many levels of subdirectories a recursive search descends into, or `None` for no limit
+	/// 
+	/// Like `find -maxdepth`, counted from `directory`: `0` only processes the entries of `directory` itself,
+	/// `1` also those of its immediate subdirectories, and so on. It has no effect unless `recursive` is set
+	pub fn max_depth(mut self, max_depth: Option<usize>) -> FindOptions {
+		self.max_depth = max_depth;
+		self
+	}
+
+	/// Whether `filenames` are compared ignoring case
+	/// 
+	/// Only ASCII letters are folded (`README.md` matches `readme.md`, but `Ä` does not match `ä`)
+	pub fn case_insensitive(mut self, case_insensitive: bool) -> FindOptions {
+		self.case_insensitive = case_insensitive;
+		self
+	}
+
+	/// Whether `filenames` are compared with the path of each entry relative to `directory`, instead of its name
+	/// 
+	/// This tells apart files sharing a name in different subdirectories: `config/server.toml` only matches
+	/// `server.toml` inside `config`. Components are separated by `/` on every platform, and a name without
+	/// any `/` only matches entries directly in `directory`
+	pub fn relative_paths(mut self, relative_paths: bool) -> FindOptions {
+		self.relative_paths = relative_paths;
+		self
+	}
+
+	/// Whether the paths of the entries selected are made canonical (absolute, with every symlink and `..`
+	/// resolved by `std::fs::canonicalize`) before they are processed or returned
+	/// 
+	/// Entries are still matched and walked through their path inside `directory`, so two symlinks to the same
+	/// file are both processed, with the same path. An entry whose path cannot be made canonical (removed during
+	/// the search, a broken symlink) fails with `IoError` unless `ignore_fail` is set, in which case it is skipped
+	pub fn canonicalize(mut self, canonicalize: bool) -> FindOptions {
+		self.canonicalize = canonicalize;
+		self
+	}
+
+	/// Whether the paths of the entries selected are made relative to `directory` before they are processed or
+	/// returned, like `textures/icon.png` instead of `./assets/textures/icon.png`, for manifests and keys
+	/// 
+	/// Unlike `relative_paths` it does not change how entries are matched. Such paths cannot be opened as they
+	/// are unless the working directory is `directory`, join them to it first. With `canonicalize` they are made
+	/// relative to the canonical `directory`, and entries resolving outside of it keep their absolute path
+	pub fn relative(mut self, relative: bool) -> FindOptions {
+		self.relative = relative;
+		self
+	}
+
+	/// Whether names that are not valid Unicode are still matched, once converted with `OsStr::to_string_lossy`
+	/// 
+	/// Otherwise such entries fail with `InvalidUnicodeData` (or are skipped with `ignore_fail`) when the search has
+	/// name criteria. Every invalid sequence becomes `U+FFFD` (`\u{FFFD}`), so names differing only in their invalid
+	/// bytes are matched alike: request them with the replacement character, like `report\u{FFFD}.txt`.
+	/// The paths given to `process` are never converted
+	pub fn lossy_names(mut self, lossy_names: bool) -> FindOptions {
+		self.lossy_names = lossy_names;
+		self
+	}
+
+	/// What to do with the symbolic links met during the search
+	pub fn symlinks(mut self, symlinks: SymlinkPolicy) -> FindOptions {
+		self.symlinks = symlinks;
+		self
+	}
+
+	/// Whether hidden entries (whose name starts with `.`) are included
+	/// 
+	/// Hidden directories are not descended into when it is unset. On Windows only the leading `.` is checked,
+	/// unless the `hidden_attribute` feature is enabled
+	pub fn include_hidden(mut self, include_hidden: bool) -> FindOptions {
+		self.include_hidden = include_hidden;
+		self
+	}
+
+	/// The order entries are processed in within each directory, or `None` for the order of the filesystem
+	/// 
+	/// Sorting buffers the listing of every directory into a `Vec` first (see `SortOrder`)
+	pub fn sort(mut self, sort: Option<SortOrder>) -> FindOptions {
+		self.sort = sort;
+		self
+	}
+
+	/// Let the search be cancelled through `token`, which is checked before every entry
+	/// 
+	/// A cancelled search stops and returns `Error::Cancelled`, even when `ignore_fail` is set
+	pub fn cancellation(mut self, token: CancellationToken) -> FindOptions {
+		self.cancellation = Some(token);
+		self
+	}
+
+	/// Stop the search with `Error::TimedOut` once it has run for longer than `timeout`, or never with `None`
+	/// 
+	/// The clock starts with the search and is checked before every entry, like `cancellation`, even when
+	/// `ignore_fail` is set. It is best-effort rather than a hard limit: listing a directory or reading
+	/// metadata can block (on a hung network filesystem), and the timeout is only noticed once they return.
+	/// The time spent in the processing function counts as well
+	pub fn timeout(mut self, timeout: Option<Duration>) -> FindOptions {
+		self.timeout = timeout;
+		self
+	}
+
+	/// Call `hook` on every entry examined, matched or skipped (with the reason why), to find out why a file is not matched
+	/// 
+	/// Events are not logged through the `log` crate: `hook` is the place to forward them to a logger. Without a hook there is no cost beyond a check per entry.
+	/// With `log` added to the dependencies of the caller, an adapter is a single function:
+	/// 
+	/// ```text
+	/// fn log_event(event: &file_processor::TraceEvent) {
+	///     log::trace!(target: "file_processor", "{:?}", event);
+	/// }
+	/// 
+	/// FindOptions::new("./assets").trace(log_event).run_with(|path| process(path))?;
+	/// ```
+	/// 
+	/// ```
+	/// use file_processor::{FindOptions, TraceEvent};
+	/// 
+	/// fn log_skips(event: &TraceEvent) {
+	///     if let TraceEvent::Skipped(path, reason) = event {
+	///         eprintln!("skipped {}: {:?}", path.display(), reason);
+	///     }
+	/// }
+	/// 
+	/// let found = FindOptions::new(".").filenames(vec![String::from("Cargo.toml")]).trace(log_skips).run_with(|_| ());
+	/// ```
+	pub fn trace(mut self, hook: fn(&TraceEvent)) -> FindOptions {
+		self.trace = Some(hook);
+		self
+	}
+
+	/// Whether to search without processing anything, to preview what a destructive run would touch
+	/// 
+	/// The entries are selected as usual and counted as processed, but the `process` closures of the `run_*`
+	/// methods (and of `run_async`, `fold_newest_first` and the watchers) are never called. `copy_into` and
+	/// `delete_files` leave the files alone and return what they would have copied or deleted. `plan` lists
+	/// the selection, so a CLI can print it behind a `--dry-run` flag:
+	/// 
+	/// ```
+	/// use file_processor::FindOptions;
+	/// 
+	/// let options = FindOptions::new(".").extensions(vec![String::from("tmp")]).dry_run(true);
+	/// let planned = options.plan();
+	/// // Nothing is deleted
+	/// let report = options.delete_files();
+	/// ```
+	pub fn dry_run(mut self, dry_run: bool) -> FindOptions {
+		self.dry_run = dry_run;
+		self
+	}
+
+	/// Run the search, calling `process` on every entry selected
+	/// 
+	/// # Variables
+	///   `process` - Closure that takes a PathBuf to an entry (`FnMut(&PathBuf)`)
+	///       It can return a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+	/// 
+	/// # Return type
+	///   `Result<usize, file_processor::Error>` - The number of entries processed
+	/// 
+	/// # Errors
+	///   `InvalidPattern(String)` - A glob pattern or the regular expression could not be compiled
+	///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+	///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+	///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+	///   `NullDirectory` - The provided directory is null
+	///   `UnexpectedSymlink(std::path::PathBuf)` - A symbolic link was found while `symlinks` is `SymlinkPolicy::Error`
+	///   `IoError(std::io::Error)` - A directory, an existing ignore file or the metadata of a file
+	///       could not be read. With `ignore_fail`, only the searched directory and ignore files
+	///   `InvalidUnicodeData` - A file has invalid characters in its name
+	///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) that have not been found
+	///   `Cancelled` - The `cancellation` token was cancelled
+	///   `TimedOut` - The search ran for longer than its `timeout`
+	pub fn run_with<R: IntoControlFlow>(&self, mut process: impl FnMut(&PathBuf) -> R) -> Result<usize, Error> {
+		self.run(&mut |path| Ok(process(path).into_control_flow()))
+	}
+
+	/// Run the search and return the paths `run_with` would process, whether `dry_run` is set or not
+	/// 
+	/// # Return type
+	///   `Result<Vec<PathBuf>, file_processor::Error>` - The paths selected, in the order they were found
+	/// 
+	/// # Errors
+	///   The same as `run_with`
+	pub fn plan(&self) -> Result<Vec<PathBuf>, Error> {
+		let mut paths = Vec::new();
+
+		self.clone().dry_run(false).run_with(|path| paths.push(path.clone()))?;
+
+		Ok(paths)
+	}
+
+	/// Run the search like `run_with`, calling `on_progress` on every entry examined (processed or not)
+	/// 
+	/// `on_progress` receives the number of entries examined so far and their total, which is only known
+	/// (`Some`) when the search is not `recursive`: the listing of `directory` is then read before its first entry.
+	/// It is called before the entry is processed, so a CLI spinner or a progress bar can be drawn without
+	/// the crate depending on any UI library
+	/// 
+	/// # Variables
+	///   `process` - Closure that takes a PathBuf to an entry (`FnMut(&PathBuf)`)
+	///       It can return a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+	///   `on_progress` - Closure that takes the count of entries examined and their total (`FnMut(usize, Option<usize>)`)
+	/// 
+	/// # Return type
+	///   `Result<usize, file_processor::Error>` - The number of entries processed
+	/// 
+	/// # Errors
+	///   The same as `run_with`
+	pub fn run_with_progress<R: IntoControlFlow>(&self, mut process: impl FnMut(&PathBuf) -> R, mut on_progress: impl FnMut(usize, Option<usize>)) -> Result<usize, Error> {
+		search::run(self, Some(&mut on_progress), &mut |path| Ok(process(path).into_control_flow()))
+	}
+
+	/// Run the search from async code, awaiting the future `process` returns for every entry selected
+	/// 
+	/// Requires the `async` feature. The directory is walked on a background thread started for the search,
+	/// so the executor is never blocked by the filesystem, and the walk gets at most 64 paths ahead of `process`.
+	/// The futures are awaited one after the other, in the order the entries were found. It does not depend
+	/// on a runtime (tokio, async-std...), and dropping the future stops the walk
+	/// 
+	/// # Variables
+	///   `process` - Closure that takes the path to an entry and returns a future (`FnMut(PathBuf) -> impl Future`)
+	///       The future can output a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+	/// 
+	/// # Return type
+	///   `Result<usize, file_processor::Error>` - The number of entries processed
+	/// 
+	/// # Errors
+	///   The same as `run_with`
+	#[cfg(feature = "async")]
+	pub async fn run_async<R: IntoControlFlow, F: std::future::Future<Output = R>>(&self, process: impl FnMut(PathBuf) -> F) -> Result<usize, Error> {
+		asynchronous::run(self, process).await
+	}
+
+	/// Run the search like `run_with`, handing `process` the metadata of every entry selected along with its path
+	/// 
+	/// The metadata is the one read while walking, following symlinks like `std::fs::metadata`, so reporting
+	/// sizes or permissions does not cost a second call per entry
+	/// 
+	/// # Variables
+	///   `process` - Closure that takes the path to an entry and its metadata (`FnMut(&Path, &Metadata)`)
+	///       It can return a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+	/// 
+	/// # Return type
+	///   `Result<usize, file_processor::Error>` - The number of entries processed
+	/// 
+	/// # Errors
+	///   The same as `run_with`, or `IoError(std::io::Error)` when the metadata of an entry could not be read
+	///   (like a broken symlink). With `ignore_fail` such entries are skipped
+	pub fn run_with_metadata<R: IntoControlFlow>(&self, mut process: impl FnMut(&Path, &std::fs::Metadata) -> R) -> Result<usize, Error> {
+		self.run_metadata(&mut |path, metadata| Ok(Some(process(path, metadata).into_control_flow())))
+	}
+
+	/// Run the search like `run_with`, handing `process` the last modification date of every entry selected
+	/// along with its path
+	/// 
+	/// The date comes from the metadata read while walking (see `run_with_metadata`), following symlinks, so
+	/// deciding whether an output is out of date does not cost a second call per entry. It can be compared with
+	/// `FileModify::date`
+	/// 
+	/// # Variables
+	///   `process` - Closure that takes the path to an entry and its modification date (`FnMut(&Path, SystemTime)`)
+	///       It can return a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+	/// 
+	/// # Return type
+	///   `Result<usize, file_processor::Error>` - The number of entries processed
+	/// 
+	/// # Errors
+	///   The same as `run_with_metadata`, or `IoError(std::io::Error)` when the modification date of an entry is not
+	///   available on the platform. With `ignore_fail` such entries are skipped
+	pub fn run_with_modified<R: IntoControlFlow>(&self, mut process: impl FnMut(&Path, std::time::SystemTime) -> R) -> Result<usize, Error> {
+		self.run_metadata(&mut |path, metadata| match metadata.modified() {
+			Ok(date) => Ok(Some(process(path, date).into_control_flow())),
+			Err(_) if self.ignore_fail => Ok(None),
+			Err(err) => Err(Error::IoError(err)),
+		})
+	}
+
+	/// Run the search, calling `process` on every entry selected with its metadata, which returns `None`
+	/// to skip an entry without counting it
+	fn run_metadata(&self, process: &mut MetadataCallback) -> Result<usize, Error> {
+		let mut search = search::Search::new(self.clone(), false, false);
+		let mut count = 0;
+
+		while let Some(next) = search.next_entry(&mut |_, _| ()) {
+			let path = next?;
+			let read_again;
+
+			let metadata = match search.metadata() {
+				Some(metadata) => metadata,
+				None => match std::fs::metadata(&path) {
+					Ok(metadata) => {
+						read_again = metadata;
+						&read_again
+					},
+					Err(_) if self.ignore_fail => continue,
+					Err(err) => return Err(Error::IoError(err)),
+				},
+			};
+
+			if self.dry_run {
+				count += 1;
+				continue;
+			}
+
+			let flow = match process(&path, metadata)? {
+				Some(flow) => flow,
+				None => continue,
+			};
+
+			count += 1;
+
+			if flow.is_break() {
+				break;
+			}
+		}
+
+		Ok(count)
+	}
+
+	/// Run the search, then fold every entry selected into a running state from the newest to the oldest
+	/// 
+	/// Unlike `sort`, the entries of all the directories walked are ordered together, by the modification date of
+	/// their metadata (read like `run_with_metadata`). Entries without a date come last, and entries with the same
+	/// date keep the order in which they were found. The whole search runs before `fold` is first called.
+	/// This keeps the newest 500 MB of logs and lists the rest to be deleted:
+	/// 
+	/// ```
+	/// use file_processor::FindOptions;
+	/// use std::ops::ControlFlow;
+	/// 
+	/// let (_, old) = FindOptions::new(".")
+	///     .extensions(vec![String::from("log")])
+	///     .recursive(true)
+	///     .fold_newest_first((0, Vec::new()), |(kept, mut old), path, metadata| {
+	///         if kept + metadata.len() > 500 * 1024 * 1024 {
+	///             old.push(path.to_path_buf());
+	///             ControlFlow::Continue((kept, old))
+	///         } else {
+	///             ControlFlow::Continue((kept + metadata.len(), old))
+	///         }
+	///     })
+	///     .unwrap();
+	/// ```
+	/// 
+	/// # Variables
+	///   `init` - The state before the first entry
+	///   `fold` - Closure that takes the state, the path to an entry and its metadata and returns the next state
+	///       (`FnMut(S, &Path, &Metadata) -> ControlFlow<S, S>`). `Break` stops with its state as the result
+	/// 
+	/// # Return type
+	///   `Result<S, file_processor::Error>` - The state after the last entry folded
+	/// 
+	/// # Errors
+	///   The same as `run_with_metadata`. `fold` is not called when the search fails
+	pub fn fold_newest_first<S>(&self, init: S, mut fold: impl FnMut(S, &Path, &std::fs::Metadata) -> ControlFlow<S, S>) -> Result<S, Error> {
+		let mut entries = Vec::new();
+
+		self.run_with_metadata(|path, metadata| entries.push((path.to_path_buf(), metadata.clone())))?;
+
+		entries.sort_by_cached_key(|(_, metadata)| std::cmp::Reverse(metadata.modified().ok()));
+
+		let (ControlFlow::Continue(state) | ControlFlow::Break(state)) = entries.iter().try_fold(init, |state, (path, metadata)| fold(state, path, metadata));
+
+		Ok(state)
+	}
+
+	/// Poll the search every `poll_interval` on a background thread, calling `process` on the entries whose
+	/// modification date changed since the previous poll
+	/// 
+	/// The first poll runs before returning, to record the dates `process` is compared against and to report
+	/// configuration errors right away. Entries appearing later (like a file saved by renaming a new one over it)
+	/// are processed as changed, removed ones are forgotten. Requested `filenames` that are missing are not
+	/// an error while watching, they are processed once they show up. Polling is `FindOptions::run_with_metadata`
+	/// run again, so its cost depends on the size of the tree walked. A longer `poll_interval` keeps the overhead
+	/// down on battery-powered devices, and on Linux `watch_events` only searches again when something changed
+	/// 
+	/// # Variables
+	///   `process` - Closure that takes a PathBuf to a changed entry (`FnMut(&PathBuf) + Send`)
+	///   `poll_interval` - The time waited between the end of a poll and the start of the next one
+	/// 
+	/// # Return type
+	///   `Result<WatchHandle, file_processor::Error>` - The running watcher, stopped when dropped
+	/// 
+	/// # Errors
+	///   The same as `run_with_metadata` for the first poll, except `MissingFiles`. The errors of later polls
+	///   stop the watcher and are returned by `WatchHandle::stop`
+	pub fn watch(&self, mut process: impl FnMut(&PathBuf) + Send + 'static, poll_interval: Duration) -> Result<WatchHandle, Error> {
+		let options = self.clone();
+		let stopped = CancellationToken::new();
+		let mut known: HashMap<PathBuf, std::time::SystemTime> = poll_dates(&options)?.into_iter().collect();
+
+		let token = stopped.clone();
+		let thread = std::thread::spawn(move || {
+			loop {
+				// Parked rather than asleep, so `WatchHandle::stop` wakes the thread up right away
+				let deadline = std::time::Instant::now().checked_add(poll_interval);
+				while !token.is_cancelled() {
+					match deadline.map(|deadline| deadline.saturating_duration_since(std::time::Instant::now())) {
+						Some(left) if left.is_zero() => break,
+						Some(left) => std::thread::park_timeout(left),
+						None => std::thread::park(),
+					}
+				}
+
+				if token.is_cancelled() {
+					return Ok(());
+				}
+
+				let current = poll_dates(&options)?;
+				for (path, date) in &current {
+					if known.get(path) != Some(date) {
+						process(path);
+					}
+				}
+
+				known = current.into_iter().collect();
+			}
+		});
+
+		Ok(WatchHandle{ stopped, thread: Some(thread), #[cfg(all(target_os = "linux", feature = "watch"))] wake: None })
+	}
+
+	/// Watch the entries selected by the search on a background thread, driven by the change notifications of
+	/// the kernel, calling `process` on each one created, modified or removed along with what happened to it
+	/// 
+	/// Requires the `watch` feature, and is only available on Linux (`inotify`): on other platforms the feature
+	/// enables nothing, and `watch` polls instead. The thread sleeps until a watched directory changes, instead
+	/// of polling at an interval, then runs the search again and compares the modification dates with the
+	/// previous ones, so the criteria are the same as for any other search. A burst of changes is handled by
+	/// a single search, once no change followed for 50 milliseconds, or a second after its first change
+	/// when the changes do not stop. With `recursive` every subdirectory is watched, including the ones
+	/// created later. Requested `filenames` that are missing are not an error, they are reported as
+	/// created once they show up. Events are reported in the order the entries are found, removals last
+	/// 
+	/// # Variables
+	///   `process` - Closure that takes a PathBuf to a changed entry and what happened to it (`FnMut(&PathBuf, WatchEvent) + Send`)
+	/// 
+	/// # Return type
+	///   `Result<WatchHandle, file_processor::Error>` - The running watcher, stopped when dropped
+	/// 
+	/// # Errors
+	///   The same as `run_with_metadata` for the first search, except `MissingFiles`, or `IoError(std::io::Error)`
+	///   when the notifications could not be set up (too many watches...). The errors of later searches
+	///   stop the watcher and are returned by `WatchHandle::stop`
+	#[cfg(all(target_os = "linux", feature = "watch"))]
+	pub fn watch_events(&self, mut process: impl FnMut(&PathBuf, WatchEvent) + Send + 'static) -> Result<WatchHandle, Error> {
+		let options = self.clone();
+		let stopped = CancellationToken::new();
+		let (notifications, wake) = inotify::Inotify::new()?;
+
+		// Watched before the first search, so no change made after it is missed
+		watch_directories(&notifications, &options)?;
+		let mut known: HashMap<PathBuf, std::time::SystemTime> = poll_dates(&options)?.into_iter().collect();
+
+		let thread = std::thread::spawn(move || {
+			while notifications.wait()? {
+				if options.recursive {
+					watch_directories(&notifications, &options)?;
+				}
+
+				let current = poll_dates(&options)?;
+				for (path, date) in &current {
+					match known.remove(path) {
+						None => process(path, WatchEvent::Created),
+						Some(previous) if previous != *date => process(path, WatchEvent::Modified),
+						Some(_) => {},
+					}
+				}
+
+				// What is left was not found again
+				let mut removed: Vec<PathBuf> = known.into_keys().collect();
+				removed.sort();
+				for path in &removed {
+					process(path, WatchEvent::Removed);
+				}
+
+				known = current.into_iter().collect();
+			}
+
+			Ok(())
+		});
+
+		Ok(WatchHandle{ stopped, thread: Some(thread), wake: Some(wake) })
+	}
+
+	/// Run the search, calling `process` on every entry selected and collecting the failures instead of stopping
+	/// 
+	/// Every failure limited to one entry is recorded along with its path and the search goes on with the rest,
+	/// whatever `ignore_fail` is. This covers entries that could not be examined, unreadable subdirectories,
+	/// invalid names or metadata, and the errors returned by `process`. Missing `filenames` are reported apart,
+	/// in `ProcessReport::missing`
+	/// 
+	/// # Variables
+	///   `process` - Closure that takes a PathBuf to an entry and returns what it made of it (`FnMut(&PathBuf) -> Result<T, Error>`)
+	/// 
+	/// # Return type
+	///   `Result<ProcessReport<T>, file_processor::Error>` - What `process` returned for every entry it succeeded on,
+	///       every failure with its path, both in the order they happened, and the missing `filenames`
+	/// 
+	/// # Errors
+	///   `InvalidPattern(String)` - A glob pattern or the regular expression could not be compiled
+	///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+	///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+	///   `NullDirectory` - The provided directory is null
+	///   `UnexpectedSymlink(std::path::PathBuf)` - A symbolic link was found while `symlinks` is `SymlinkPolicy::Error`
+	///   `IoError(std::io::Error)` - An existing ignore file could not be read
+	///   `Cancelled` - The `cancellation` token was cancelled
+	///   `TimedOut` - The search ran for longer than its `timeout`
+	pub fn run_collecting_errors<T>(&self, mut process: impl FnMut(&PathBuf) -> Result<T, Error>) -> Result<ProcessReport<T>, Error> {
+		search::run_collecting(self, &mut process)
+	}
+
+	/// Run the search and copy every file selected into `destination`, creating it if needed
+	/// 
+	/// Directories are not copied. The files are all found before the first one is copied, so a `destination`
+	/// inside the searched directory is not searched again. Existing files in `destination` are overwritten
+	/// 
+	/// # Variables
+	///   `destination` - The directory to copy the files into (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+	///   `preserve_subpaths` - A boolean indicating if the files keep their path relative to `directory`
+	///       (`sub/a.txt` is copied to `destination/sub/a.txt`). Otherwise they are all copied directly
+	///       into `destination`, and files sharing a name in different subdirectories overwrite each other
+	/// 
+	/// # Return type
+	///   `Result<usize, file_processor::Error>` - The number of files copied, or that would have been with `dry_run`
+	/// 
+	/// # Errors
+	///   The same as `run_with`, or `IoError(std::io::Error)` when `destination` or a file could not be written.
+	///   With `ignore_fail` the files that cannot be copied are skipped
+	pub fn copy_into(&self, destination: impl AsRef<Path>, preserve_subpaths: bool) -> Result<usize, Error> {
+		let destination = destination.as_ref();
+		let mut paths = Vec::new();
+
+		// Subpaths are taken from the paths inside `directory`
+		self.clone().entry_kind(EntryKind::FilesOnly).canonicalize(false).relative(false).dry_run(false).run_with(|path| paths.push(path.clone()))?;
+
+		if self.dry_run {
+			return Ok(paths.len());
+		}
+
+		std::fs::create_dir_all(destination).map_err(Error::IoError)?;
+
+		let mut copied = 0;
+
+		for path in paths {
+			let target = match (preserve_subpaths, path.strip_prefix(&self.directory)) {
+				(true, Ok(relative)) => destination.join(relative),
+				_ => destination.join(path.file_name().unwrap_or_default()),
+			};
+
+			let result = match target.parent() {
+				Some(parent) => std::fs::create_dir_all(parent),
+				None => Ok(()),
+			}.and_then(|_| std::fs::copy(&path, &target));
+
+			match result {
+				Ok(_) => copied += 1,
+				Err(err) => if !self.ignore_fail { return Err(Error::IoError(err)) },
+			}
+		}
+
+		Ok(copied)
+	}
+
+	/// Run the search and delete every file selected, going on after every failure and returning them all
+	/// 
+	/// Directories are never deleted. With `dry_run` set nothing is deleted either, and the files that would
+	/// have been are returned instead
+	/// 
+	/// # Return type
+	///   `Result<ProcessReport<PathBuf>, file_processor::Error>` - The deleted files, and every failure with its path
+	///       (see `run_collecting_errors`)
+	/// 
+	/// # Errors
+	///   The same as `run_collecting_errors`
+	pub fn delete_files(&self) -> Result<ProcessReport<PathBuf>, Error> {
+		// The files are deleted through their full path
+		self.clone().entry_kind(EntryKind::FilesOnly).relative(false).dry_run(false).run_collecting_errors(|path| {
+			if !self.dry_run {
+				std::fs::remove_file(path)?;
+			}
+
+			Ok(path.clone())
+		})
+	}
+
+	/// Run the search with a `process` that can fail, stopping the search with its error
+	fn run(&self, process: &mut dyn FnMut(&PathBuf) -> Result<ControlFlow<()>, Error>) -> Result<usize, Error> {
+		search::run(self, None, process)
+	}
+}
+
+
+
+/// A search driven as an iterator over the paths it selects, configured by `FindOptions`
+/// 
+/// Nothing is read before the first call to `next`, and each call walks the directory just far enough
+/// to find the next entry, so `take` or an early `break` never list what comes after:
+/// 
+/// ```
+/// use file_processor::{FileFinder, FindOptions};
+/// 
+/// let sources = FileFinder::new(FindOptions::new("./src").extensions(vec![String::from("rs")]).recursive(true))
+///     .take(10)
+///     .collect::<Result<Vec<_>, _>>();
+/// ```
+/// 
+/// Every error `FindOptions::run_with` can return is yielded as an `Err`, after which the iterator is over.
+/// Missing `filenames` are yielded last, as `MissingFiles`
+pub struct FileFinder {
+	search: search::Search,
+}
+
+impl FileFinder {
+	pub fn new(options: FindOptions) -> FileFinder {
+		FileFinder{
+			search: search::Search::new(options, false, false),
+		}
+	}
+}
+
+impl Iterator for FileFinder {
+	type Item = Result<PathBuf, Error>;
+
+	fn next(&mut self) -> Option<Result<PathBuf, Error>> {
+		self.search.next_entry(&mut |_, _| ())
+	}
+}
+
+impl IntoIterator for FindOptions {
+	type Item = Result<PathBuf, Error>;
+	type IntoIter = FileFinder;
+
+	fn into_iter(self) -> FileFinder {
+		FileFinder::new(self)
+	}
+}
+
+
+/// How the `*_load*` functions turn a file into bytes, to decompress or decrypt files while they are loaded
+/// 
+/// Closures taking a `&Path` and returning a `Result<Vec<u8>, file_processor::Error>` are loaders too.
+/// `RawLoader` reads files as they are:
+/// 
+/// ```
+/// use file_processor::{Error, FileLoader, RawLoader};
+/// use std::path::Path;
+/// 
+/// struct Uppercase;
+/// 
+/// impl FileLoader for Uppercase {
+///     fn load(&self, path: &Path) -> Result<Vec<u8>, Error> {
+///         Ok(RawLoader.load(path)?.to_ascii_uppercase())
+///     }
+/// }
+/// ```
+pub trait FileLoader {
+	/// The contents of the file at `path`
+	fn load(&self, path: &Path) -> Result<Vec<u8>, Error>;
+}
+
+/// The loader reading files as they are, with `std::fs::read`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RawLoader;
+
+impl FileLoader for RawLoader {
+	fn load(&self, path: &Path) -> Result<Vec<u8>, Error> {
+		std::fs::read(path).map_err(Error::IoError)
+	}
+}
+
+impl<F: Fn(&Path) -> Result<Vec<u8>, Error>> FileLoader for F {
+	fn load(&self, path: &Path) -> Result<Vec<u8>, Error> {
+		self(path)
+	}
+}
+
+
+/// Find all files provided in `filenames`, run them through the provided function `process`
+/// and then load the files provided by the function as byte vectors (binary format)
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `process` - Closure that takes a PathBuf to a file and returns a PathBuf to the new file created (`FnMut(&PathBuf) -> PathBuf`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<Vec<Vec<u8>>, file_processor::Error>`
+///       Each file is read into an owned buffer, freed normally when dropped
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory could not be read (permissions, removed during the search...)
+///       or a file returned by `process` could not be read
+///   `InvalidUnicodeData` - A file has invalid characters in its extension
+///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) that have not been found
+pub fn find_and_then_and_load(directory: impl AsRef<Path>, filenames: Vec<String>, process: impl FnMut(&PathBuf) -> PathBuf, ignore_fail: bool, recursive: bool) -> Result<Vec<Vec<u8>>, Error> {
+	find_and_then_and_load_with(directory, filenames, process, RawLoader, ignore_fail, recursive)
+}
+
+
+/// Same as `find_and_then_and_load`, loading the files provided by `process` with `loader`
+/// 
+/// # Variables
+///   `loader` - How the files are loaded (see `FileLoader`)
+/// 
+/// # Errors
+///   The same as `find_and_then_and_load`, or the failures of `loader`
+pub fn find_and_then_and_load_with(directory: impl AsRef<Path>, filenames: Vec<String>, mut process: impl FnMut(&PathBuf) -> PathBuf, loader: impl FileLoader, ignore_fail: bool, recursive: bool) -> Result<Vec<Vec<u8>>, Error> {
+	let mut binaries: Vec<_> = Vec::new();
+
+	FindOptions::new(directory).filenames(filenames).ignore_fail(ignore_fail).recursive(recursive).run(&mut |path| {
+		binaries.push(loader.load(&process(path))?);
+		Ok(ControlFlow::Continue(()))
+	})?;
+
+	Ok(binaries)
+}
+
+
+/// Find all files provided in `filenames` and load their contents, keyed by their filename
+/// 
+/// Unlike `find_and_then_and_load` the contents of a file are looked up by its name instead of its position.
+/// When several files share a requested name (in different subdirectories), the first one found is kept
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned, otherwise unreadable files are left out
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<HashMap<String, Vec<u8>>, file_processor::Error>` - The contents of every file, keyed by its name
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory or a file could not be read
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) that have not been found
+pub fn find_and_load_by_name(directory: impl AsRef<Path>, filenames: Vec<String>, ignore_fail: bool, recursive: bool) -> Result<HashMap<String, Vec<u8>>, Error> {
+	find_and_load_by_name_with(directory, filenames, RawLoader, ignore_fail, recursive)
+}
+
+
+/// Same as `find_and_load_by_name`, loading the files with `loader`
+/// 
+/// # Variables
+///   `loader` - How the files are loaded (see `FileLoader`). With `ignore_fail` the files it fails on are left out
+/// 
+/// # Errors
+///   The same as `find_and_load_by_name`, or the failures of `loader`
+pub fn find_and_load_by_name_with(directory: impl AsRef<Path>, filenames: Vec<String>, loader: impl FileLoader, ignore_fail: bool, recursive: bool) -> Result<HashMap<String, Vec<u8>>, Error> {
+	let mut contents = HashMap::new();
+
+	FindOptions::new(directory).filenames(filenames).entry_kind(EntryKind::FilesOnly).ignore_fail(ignore_fail).recursive(recursive).run(&mut |path| {
+		let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+
+		if let std::collections::hash_map::Entry::Vacant(entry) = contents.entry(name) {
+			match loader.load(path) {
+				Ok(bytes) => { entry.insert(bytes); },
+				Err(err) => if !ignore_fail { return Err(err) },
+			}
+		}
+
+		Ok(ControlFlow::Continue(()))
+	})?;
+
+	Ok(contents)
+}
+
+
+/// Find the file named `filename` and return its contents
+/// 
+/// The single file counterpart of `find_and_load_by_name`. `directory` and its subdirectories are searched, so
+/// a name present twice in the tree is reported instead of one of the files being picked. Symlinks are followed
+/// and hidden files included
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filename` - The name of the file to load
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+/// 
+/// # Return type
+///   `Result<Vec<u8>, file_processor::Error>` - The contents of the file
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory or the file could not be read
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - No file is named `filename`
+///   `Ambiguous(Vec<std::path::PathBuf>)` - The paths of all the files named `filename`, when there are several
+pub fn load_one(directory: impl AsRef<Path>, filename: &str, ignore_fail: bool) -> Result<Vec<u8>, Error> {
+	load_one_with(directory, filename, RawLoader, ignore_fail)
+}
+
+
+/// Same as `load_one`, loading the file with `loader`
+/// 
+/// # Variables
+///   `loader` - How the file is loaded (see `FileLoader`)
+/// 
+/// # Errors
+///   The same as `load_one`, or the failures of `loader`
+pub fn load_one_with(directory: impl AsRef<Path>, filename: &str, loader: impl FileLoader, ignore_fail: bool) -> Result<Vec<u8>, Error> {
+	let mut paths = Vec::new();
+
+	FindOptions::new(directory).filenames(vec![filename.to_string()]).entry_kind(EntryKind::FilesOnly).ignore_fail(ignore_fail).recursive(true).run_with(|path| paths.push(path.clone()))?;
+
+	match paths.len() {
+		1 => loader.load(&paths[0]),
+		_ => Err(Error::Ambiguous(paths)),
+	}
+}
+
+
+/// Find all files provided in `filenames` and compute the digest of their contents
+/// 
+/// Files are read in chunks, so they are never loaded in memory as a whole. Only `directory` itself is searched,
+/// following symlinks and including hidden files. Digests are lowercase hexadecimal strings, as printed by
+/// `sha256sum` or `md5sum`
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `algorithm` - The digest to compute
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned, otherwise unreadable files are skipped
+/// 
+/// # Return type
+///   `Result<Vec<(PathBuf, String)>, file_processor::Error>` - The path and digest of every file, in the order they were found
+/// 
+/// # Errors
+///   `UnsupportedAlgorithm(HashAlgorithm)` - The cargo feature enabling `algorithm` is not compiled in
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - The directory or a file could not be read
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) that have not been found
+pub fn find_and_hash(directory: impl AsRef<Path>, filenames: Vec<String>, algorithm: HashAlgorithm, ignore_fail: bool) -> Result<Vec<(PathBuf, String)>, Error> {
+	hash::check(algorithm)?;
+
+	let mut digests = Vec::new();
+
+	FindOptions::new(directory).filenames(filenames).ignore_fail(ignore_fail).run(&mut |path| {
+		match hash::digest_file(path, algorithm) {
+			Ok(digest) => digests.push((path.clone(), digest)),
+			Err(err) => if !ignore_fail { return Err(err) },
+		}
+
+		Ok(ControlFlow::Continue(()))
+	})?;
+
+	Ok(digests)
+}
+
+
+/// Find all files provided in `filenames` and compute one digest of their contents as a whole, as the key
+/// of a build cache
+/// 
+/// Every file is hashed like in `find_and_hash`, then the digests are sorted, so the order the files are found in
+/// does not matter, and the digest of their lines (`<digest>\n` each) is returned. Changing the contents of a file,
+/// or adding or removing one, changes it, but renaming a file does not. Only `directory` itself is searched,
+/// and every file has to be read, so no failure is ignored
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `algorithm` - The digest to compute, for the files and for the whole selection
+/// 
+/// # Return type
+///   `Result<String, file_processor::Error>` - The lowercase hexadecimal digest of the selection
+/// 
+/// # Errors
+///   The same as `find_and_hash`
+pub fn selection_digest(directory: impl AsRef<Path>, filenames: Vec<String>, algorithm: HashAlgorithm) -> Result<String, Error> {
+	let mut digests: Vec<String> = find_and_hash(directory, filenames, algorithm, false)?.into_iter().map(|(_, digest)| digest).collect();
+	digests.sort_unstable();
+
+	let lines: String = digests.iter().map(|digest| format!("{}\n", digest)).collect();
+
+	hash::digest_bytes(lines.as_bytes(), algorithm)
+}
+
+
+/// Find all files provided in `filenames` and guess their MIME type
+/// 
+/// The first bytes of each file are compared with the signatures of common formats (images, audio, video,
+/// archives, documents, fonts, executables, HTML/XML/SVG markup). Files that are not recognized this way are
+/// guessed from their extension, ignoring case, then reported as `text/plain` when they look like UTF-8 text
+/// and `application/octet-stream` otherwise. Only `directory` itself is searched, following symlinks and
+/// including hidden files. Requires the `mime` feature
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned, otherwise unreadable files are skipped
+/// 
+/// # Return type
+///   `Result<Vec<(PathBuf, String)>, file_processor::Error>` - The path and MIME type (`image/png`) of every file,
+///       in the order they were found
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - The directory or a file could not be read
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) that have not been found
+#[cfg(feature = "mime")]
+pub fn find_and_detect_mime(directory: impl AsRef<Path>, filenames: Vec<String>, ignore_fail: bool) -> Result<Vec<(PathBuf, String)>, Error> {
+	let mut types = Vec::new();
+
+	FindOptions::new(directory).filenames(filenames).ignore_fail(ignore_fail).run(&mut |path| {
+		match mime::detect_file(path) {
+			Ok(mime) => types.push((path.clone(), mime)),
+			Err(err) => if !ignore_fail { return Err(err) },
+		}
+
+		Ok(ControlFlow::Continue(()))
+	})?;
+
+	Ok(types)
+}
+
+
+/// Find all files provided in `filenames` and rename each of them to the path returned by `rename`
+/// 
+/// The matched files are all found before the first one is renamed, so a renamed file is never matched again.
+/// Existing files are never overwritten: a file whose new path is taken (by a file, a directory or a link, including
+/// the file itself) is not renamed, even when the path is taken while renaming. A file renamed to another
+/// filesystem is copied then deleted. Only `directory` itself is searched, following symlinks and including
+/// hidden files
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `rename` - Closure that takes the path to a file and returns its new path (`Fn(&Path) -> PathBuf`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned, otherwise files that cannot be renamed are skipped
+/// 
+/// # Return type
+///   `Result<Vec<(PathBuf, PathBuf)>, file_processor::Error>` - The old and new path of every file renamed, in order.
+///       When a rename fails the files renamed before it are not moved back
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - The directory could not be read or a file could not be renamed.
+///       The kind is `std::io::ErrorKind::AlreadyExists` when the new path of a file is taken
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) that have not been found
+pub fn find_and_rename(directory: impl AsRef<Path>, filenames: Vec<String>, rename: impl Fn(&Path) -> PathBuf, ignore_fail: bool) -> Result<Vec<(PathBuf, PathBuf)>, Error> {
+	let mut renamed = Vec::new();
+
+	for path in find_matching(directory, filenames, ignore_fail, false)? {
+		let target = rename(&path);
+
+		match move_file(&path, &target) {
+			Ok(()) => renamed.push((path, target)),
+			Err(err) => if !ignore_fail { return Err(Error::IoError(err)) },
+		}
+	}
+
+	Ok(renamed)
+}
+
+
+/// Find all files provided in `filenames` and copy them into `destination`, creating it if needed
+/// 
+/// Only `directory` itself is searched, following symlinks and including hidden files. Shorthand for
+/// `FindOptions::new(directory).filenames(filenames).copy_into(destination, false)`, which can also search
+/// recursively and keep the relative subpaths of the files
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `destination` - The directory to copy the files into (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned, otherwise files that cannot be copied are skipped
+/// 
+/// # Return type
+///   `Result<usize, file_processor::Error>` - The number of files copied
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - The directory could not be read, or `destination` or a file could not be written
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) that have not been found
+pub fn find_and_copy(directory: impl AsRef<Path>, filenames: Vec<String>, destination: impl AsRef<Path>, ignore_fail: bool) -> Result<usize, Error> {
+	FindOptions::new(directory).filenames(filenames).ignore_fail(ignore_fail).copy_into(destination, false)
+}
+
+
+/// Find all files provided in `filenames` and delete them
+/// 
+/// Only `directory` itself is searched, following symlinks (the link is deleted, not its target) and
+/// including hidden files. Directories are never deleted. Preview what would be deleted with `dry_run`:
+/// 
+/// ```
+/// use file_processor::{EntryKind, FindOptions};
+/// 
+/// let planned = file_processor::dry_run(|record| {
+///     FindOptions::new("./build").filenames(vec![String::from("cache.bin")]).entry_kind(EntryKind::FilesOnly).run_with(record)
+/// });
+/// ```
+/// 
+/// Use `FindOptions::delete_files` to search recursively, or to collect every failure instead of stopping at the first
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned, otherwise files that cannot be deleted are skipped
+/// 
+/// # Return type
+///   `Result<Vec<PathBuf>, file_processor::Error>` - The deleted files, in the order they were found
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - The directory could not be read or a file could not be deleted
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) that have not been found
+pub fn find_and_delete(directory: impl AsRef<Path>, filenames: Vec<String>, ignore_fail: bool) -> Result<Vec<PathBuf>, Error> {
+	let mut deleted = Vec::new();
+
+	FindOptions::new(directory).filenames(filenames).entry_kind(EntryKind::FilesOnly).ignore_fail(ignore_fail).run(&mut |path| {
+		match std::fs::remove_file(path) {
+			Ok(()) => deleted.push(path.clone()),
+			Err(err) => if !ignore_fail { return Err(Error::IoError(err)) },
+		}
+
+		Ok(ControlFlow::Continue(()))
+	})?;
+
+	Ok(deleted)
+}
+
+
+/// Move the file at `from` to `to`, failing with `std::io::ErrorKind::AlreadyExists` when `to` exists
+/// 
+/// `std::fs::rename` silently replaces an existing `to` on Unix, so the file is hard linked at `to` instead, which
+/// fails when anything is there even if it was created concurrently, then unlinked from `from`. Where no link
+/// can be made (across filesystems, or on filesystems without links) it is copied into a file created at `to` only
+/// when there is none, then deleted
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+	match std::fs::hard_link(from, to) {
+		Ok(()) => std::fs::remove_file(from),
+		Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists || err.kind() == std::io::ErrorKind::NotFound => Err(err),
+		Err(_) => copy_into_new(from, to).and_then(|()| std::fs::remove_file(from)),
+	}
+}
+
+/// Copy the contents and permissions of the file at `from` into a new file at `to`, which must not exist.
+/// A partial copy is removed
+fn copy_into_new(from: &Path, to: &Path) -> std::io::Result<()> {
+	let mut source = std::fs::File::open(from)?;
+	let mut target = std::fs::OpenOptions::new().write(true).create_new(true).open(to)?;
+
+	let copied = std::io::copy(&mut source, &mut target)
+		.and_then(|_| source.metadata())
+		.and_then(|metadata| target.set_permissions(metadata.permissions()));
+
+	if copied.is_err() {
+		drop(target);
+		let _ = std::fs::remove_file(to);
+	}
+
+	copied
+}
+
+
+/// Find all files provided in `filenames` and hand a buffered reader over each of them to `process`
+/// 
+/// Unlike `find_and_then_and_load` the files are never loaded in memory as a whole, `process` reads
+/// as much as it needs. Only `directory` itself is searched, following symlinks and including hidden files
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `process` - Closure that takes the path to a file and a reader over its contents (`FnMut(&Path, &mut dyn Read)`)
+///       It can return a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned, otherwise files that cannot be opened are skipped
+/// 
+/// # Return type
+///   `Result<usize, file_processor::Error>` - The number of files processed
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - The directory could not be read or a file could not be opened
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) that have not been found
+pub fn find_and_then_streamed<R: IntoControlFlow>(directory: impl AsRef<Path>, filenames: Vec<String>, mut process: impl FnMut(&Path, &mut dyn Read) -> R, ignore_fail: bool) -> Result<usize, Error> {
+	let mut count = 0;
+
+	FindOptions::new(directory).filenames(filenames).ignore_fail(ignore_fail).run(&mut |path| {
+		match std::fs::File::open(path) {
+			Ok(file) => {
+				count += 1;
+				return Ok(process(path, &mut BufReader::new(file)).into_control_flow());
+			},
+			Err(err) => if !ignore_fail { return Err(Error::IoError(err)) },
+		}
+
+		Ok(ControlFlow::Continue(()))
+	})?;
+
+	Ok(count)
+}
+
+
+/// Find all files provided in `filenames` and open each of them for reading, to stream their contents later
+/// 
+/// Unlike `find_and_then_and_load` nothing is read yet. Every file stays open until its `File` is dropped,
+/// which is up to the caller: opening many files at once may hit the limit of open handles of the OS
+/// (`ulimit -n` on Unix), so drop each one when done with it or use `find_and_then_streamed` instead.
+/// Only `directory` itself is searched, following symlinks and including hidden files
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned, otherwise files that cannot be opened are skipped
+/// 
+/// # Return type
+///   `Result<Vec<(PathBuf, std::fs::File)>, file_processor::Error>` - The path and open handle of every file,
+///       in the order they were found. When an error is returned, the files already opened are closed
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - The directory could not be read or a file could not be opened
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) that have not been found
+pub fn find_and_open(directory: impl AsRef<Path>, filenames: Vec<String>, ignore_fail: bool) -> Result<Vec<(PathBuf, std::fs::File)>, Error> {
+	let mut files = Vec::new();
+
+	FindOptions::new(directory).filenames(filenames).ignore_fail(ignore_fail).run(&mut |path| {
+		match std::fs::File::open(path) {
+			Ok(file) => files.push((path.clone(), file)),
+			Err(err) => if !ignore_fail { return Err(Error::IoError(err)) },
+		}
+
+		Ok(ControlFlow::Continue(()))
+	})?;
+
+	Ok(files)
+}
+
+
+/// Find all files provided in `filenames` and map each of them in memory, for read-only processing of large files
+/// 
+/// Unlike `find_and_load_by_name` nothing is copied: the operating system loads the pages of a file as they are
+/// read. Only `directory` itself is searched, following symlinks and including hidden files.
+/// Requires the `memmap` feature, on Unix
+/// 
+/// # Safety
+///   A mapped file must not be modified or truncated, by this program or another one, as long as its `Mmap` lives.
+///   The view borrows the bytes of the file, so a change is undefined behavior, and reading past a truncated end
+///   crashes the program (`SIGBUS`). Only map files nothing writes to while they are used
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned, otherwise files that cannot be mapped are skipped
+/// 
+/// # Return type
+///   `Result<Vec<(PathBuf, Mmap)>, file_processor::Error>` - The path and the mapped contents of every file,
+///       in the order they were found
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - The directory could not be read, or a file could not be opened or mapped
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) that have not been found
+#[cfg(all(unix, feature = "memmap"))]
+pub unsafe fn find_and_mmap(directory: impl AsRef<Path>, filenames: Vec<String>, ignore_fail: bool) -> Result<Vec<(PathBuf, Mmap)>, Error> {
+	let mut maps = Vec::new();
+
+	FindOptions::new(directory).filenames(filenames).ignore_fail(ignore_fail).run(&mut |path| {
+		// SAFETY: the files are left unchanged while mapped, as the caller promised
+		match unsafe { Mmap::open(path) } {
+			Ok(map) => maps.push((path.clone(), map)),
+			Err(err) => if !ignore_fail { return Err(err) },
+		}
+
+		Ok(ControlFlow::Continue(()))
+	})?;
+
+	Ok(maps)
+}
+
+
+/// Find all files with the extensions provided in `extensions` and run them through the provided function `process`
+/// 
+/// Extensions are compared ignoring ASCII case, so `jpg` matches `photo.jpg`, `photo.JPG` and `photo.Jpg`.
+/// They are given without their dot (`jpg`), but a single leading `.` is accepted and stripped (`.jpg`).
+/// Compound extensions are matched against the end of the name, so `tar.gz` selects `archive.tar.gz` but not `backup.gz`.
+/// Shorthand for `FindOptions::new(directory).extensions(extensions)`, see `FindOptions` for the other options,
+/// like `exclude` to leave out some of the files
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `extensions` - A slice of all the extensions to be filtered (`jpg` or `.jpg`), compared ignoring ASCII case
+///       It is only borrowed, so the same list (`&[&str]`, `&[String]`...) can be reused for several searches
+///   `process` - Closure that takes a PathBuf to a file (`FnMut(&PathBuf)`)
+///       It can return a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<usize, file_processor::Error>` - The number of files processed
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory could not be read (permissions, removed during the search...)
+pub fn find_by_extension_and_then<R: IntoControlFlow>(directory: impl AsRef<Path>, extensions: &[impl AsRef<str>], process: impl FnMut(&PathBuf) -> R, ignore_fail: bool, recursive: bool) -> Result<usize, Error> {
+	let extensions = extensions.iter().map(|ext| ext.as_ref().to_string()).collect();
+
+	FindOptions::new(directory).extensions(extensions).ignore_fail(ignore_fail).recursive(recursive).run_with(process)
+}
+
+
+/// Find all files whose name matches any of the wildcard `patterns` and run them through the provided function `process`
+/// 
+/// Patterns are matched against the file name only, never against the rest of the path, and are case sensitive:
+///   `*` - Matches any sequence of characters, including none (`config.*` matches `config.toml` and `config.`)
+///   `?` - Matches exactly one character (`test_?.rs` matches `test_1.rs` but not `test_10.rs`)
+///   `[abc]`, `[a-z]`, `[!abc]` - Matches one character in (or with `!`, not in) the set
+/// 
+/// A leading `.` is not special, so `*` also matches dotfiles. Shorthand for `FindOptions::new(directory).globs(patterns)`,
+/// add `.exclude(patterns)` to leave out the names matching other patterns
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `patterns` - A vector of all the patterns to be matched
+///   `process` - Closure that takes a PathBuf to a file (`FnMut(&PathBuf)`), called once per matching entry
+///       It can return a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<usize, file_processor::Error>` - The number of files processed
+/// 
+/// # Errors
+///   `InvalidPattern(String)` - A pattern could not be compiled (an unclosed `[`)
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory could not be read (permissions, removed during the search...)
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+pub fn find_by_glob_and_then<R: IntoControlFlow>(directory: impl AsRef<Path>, patterns: Vec<String>, process: impl FnMut(&PathBuf) -> R, ignore_fail: bool, recursive: bool) -> Result<usize, Error> {
+	FindOptions::new(directory).globs(patterns).ignore_fail(ignore_fail).recursive(recursive).run_with(process)
+}
+
+
+/// Find all files whose name matches the regular expression `pattern` and run them through the provided function `process`
+/// 
+/// The expression is compiled once and matched against the file name only. Like most regex engines it matches
+/// anywhere in the name, so anchor it with `^` and `$` to match whole names (`^backup_\d{4}-\d{2}-\d{2}\.tar$`).
+/// Supported syntax: `.`, `^`, `$`, `[...]` sets, `\d` `\w` `\s` (ASCII only) and their negations, `(...)` groups,
+/// `|` alternation and the `*` `+` `?` `{n,m}` repetitions. Matching takes a time linear in the length of the name,
+/// whatever the expression
+/// 
+/// Requires the `regex` feature. Shorthand for `FindOptions::new(directory).regex(pattern)`
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `pattern` - The regular expression to be matched
+///   `process` - Closure that takes a PathBuf to a file (`FnMut(&PathBuf)`), called once per matching entry
+///       It can return a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<usize, file_processor::Error>` - The number of files processed
+/// 
+/// # Errors
+///   `InvalidPattern(String)` - The regular expression could not be compiled
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory could not be read (permissions, removed during the search...)
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+#[cfg(feature = "regex")]
+pub fn find_by_regex_and_then<R: IntoControlFlow>(directory: impl AsRef<Path>, pattern: &str, process: impl FnMut(&PathBuf) -> R, ignore_fail: bool, recursive: bool) -> Result<usize, Error> {
+	FindOptions::new(directory).regex(pattern).ignore_fail(ignore_fail).recursive(recursive).run_with(process)
+}
+
+
+/// What a search did with an entry, as told to the hook of `FindOptions::trace`
+#[derive(Debug, Clone, Copy)]
+pub enum TraceEvent<'a> {
+	/// The entry was read from its directory, before anything else
+	Examined(&'a Path),
+	/// The entry matches every criterion and is about to be processed
+	Matched(&'a Path),
+	/// The entry is not processed. Skipped directories are still descended into, unless they are
+	/// hidden, ignored or skipped symlinks
+	Skipped(&'a Path, SkipReason),
+}
+
+/// Why a search skipped an entry (see `TraceEvent::Skipped`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+	/// Its name starts with a `.` and `include_hidden` is unset
+	Hidden,
+	/// It is a symbolic link and `symlinks` is `SymlinkPolicy::Skip`
+	Symlink,
+	/// It is excluded by an ignore file (`ignore` feature)
+	Ignored,
+	/// It is not of the `EntryKind` searched
+	Kind,
+	/// Its name does not match `filenames`, `globs` or `regex`
+	Name,
+	/// Its name matches one of the `exclude` patterns
+	Excluded,
+	/// Its extension is not one of `extensions`
+	Extension,
+	/// Its size is out of `size`, or it is a directory and the search has size or age criteria
+	Size,
+	/// The time since it was last modified is out of `age`
+	Age,
+	/// Its contents do not start with `magic`, or it is a directory and the search has a magic number
+	Magic,
+	/// Its name or metadata could not be read, and the failure was skipped (`ignore_fail`) or collected
+	Failed,
+}
+
+
+/// A flag to cancel a running search from another thread (see `FindOptions::cancellation`)
+/// 
+/// Clones share the same flag, so a GUI can keep one and hand the other to the search
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+	cancelled: Arc<AtomicBool>,
+	/// The flag of the token this one was linked to, which cancels it too
+	parent: Option<Arc<AtomicBool>>,
+}
+
+impl CancellationToken {
+	pub fn new() -> CancellationToken {
+		CancellationToken::default()
+	}
+
+	/// A token cancelled along with this one, which can also be cancelled on its own without cancelling this one
+	#[cfg(feature = "async")]
+	pub(crate) fn linked(&self) -> CancellationToken {
+		CancellationToken{ cancelled: Arc::default(), parent: Some(self.cancelled.clone()) }
+	}
+
+	/// Cancel every search using this token (or a clone of it) before its next entry
+	pub fn cancel(&self) {
+		self.cancelled.store(true, Ordering::Relaxed);
+	}
+
+	pub fn is_cancelled(&self) -> bool {
+		self.cancelled.load(Ordering::Relaxed) || self.parent.as_ref().is_some_and(|parent| parent.load(Ordering::Relaxed))
+	}
+}
+
+
+/// The values a `process` closure can return to go on with a search or stop it
+/// 
+/// `()` and `true` go on, while `false` stops like `ControlFlow::Break(())`. A stopped search returns `Ok`
+/// with what was processed so far, without reporting the files it did not get to as missing
+pub trait IntoControlFlow {
+	fn into_control_flow(self) -> ControlFlow<()>;
+}
+
+impl IntoControlFlow for () {
+	fn into_control_flow(self) -> ControlFlow<()> {
+		ControlFlow::Continue(())
+	}
+}
+
+impl IntoControlFlow for bool {
+	fn into_control_flow(self) -> ControlFlow<()> {
+		if self { ControlFlow::Continue(()) } else { ControlFlow::Break(()) }
+	}
+}
+
+impl IntoControlFlow for ControlFlow<()> {
+	fn into_control_flow(self) -> ControlFlow<()> {
+		self
+	}
+}
+
+
+/// What to do with the symbolic links met while walking a directory (see `FindOptions::symlinks`)
+/// 
+/// The policy applies to links to files and to directories alike, and is checked on the link itself
+/// before anything else, so a skipped link is never matched nor descended into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+	/// Treat links like their target. A linked directory is only walked once, so loops are harmless
+	Follow,
+	/// Ignore links entirely
+	Skip,
+	/// Stop the search with `Error::UnexpectedSymlink`, even when `ignore_fail` is set
+	Error,
+}
+
+
+/// The kinds of entries a search processes (see `FindOptions::entry_kind`)
+/// 
+/// Whatever is not a directory (or a followed symlink to one) counts as a file. Size and age criteria
+/// never match directories, whatever the kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+	FilesOnly,
+	/// Only directories, like those named with a `.bundle` extension
+	DirsOnly,
+	Both,
+}
+
+
+/// The order in which the entries of each directory are processed (see `FindOptions::sort`)
+/// 
+/// Sorting needs the whole listing of a directory, so it is buffered into a `Vec` before its first entry
+/// is processed. Entries are sorted within their own directory and subdirectories are still walked
+/// depth-first, right after their own entry. Names are compared by their raw bytes (`Z` sorts before `a`),
+/// and modification dates follow symlinks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+	NameAsc,
+	NameDesc,
+	/// Oldest first. Entries modified at the same time are ordered by name, and the ones whose date cannot be
+	/// read come last
+	ModifiedAsc,
+	/// Newest first. Entries modified at the same time are ordered by name, and the ones whose date cannot be
+	/// read come last
+	ModifiedDesc,
+}
+
+
+/// The ignore files honored by `find_respecting_ignore` and `FindOptions::ignore_files`
+/// 
+/// The default honors all of them. Requires the `ignore` feature
+#[cfg(feature = "ignore")]
+#[derive(Debug, Clone, Copy)]
+pub struct IgnoreFiles {
+	/// `.gitignore` files in the searched directories. The `.git` directory is skipped as well
+	pub gitignore: bool,
+	/// `.ignore` files in the searched directories, which take precedence over `.gitignore`
+	pub dot_ignore: bool,
+	/// The global git excludes file (`core.excludesFile` from `~/.gitconfig`, or `~/.config/git/ignore`)
+	pub global: bool,
+}
+
+#[cfg(feature = "ignore")]
+impl Default for IgnoreFiles {
+	fn default() -> IgnoreFiles {
+		IgnoreFiles{
+			gitignore: true,
+			dot_ignore: true,
+			global: true,
+		}
+	}
+}
+
+
+/// Recursively find all files not excluded by ignore files and run them through the provided function `process`
+/// 
+/// Ignore files use the `.gitignore` syntax: `#` comments, `!` negation, a trailing `/` to only match directories,
+/// a leading or middle `/` to anchor the rule to the directory of the ignore file, and `*`, `?`, `[...]` and `**`
+/// wildcards. Rules of deeper ignore files take precedence, and within a file the last matching rule wins.
+/// Ignored directories are not descended into, so their contents cannot be re-included.
+/// Only ignore files inside `directory` are read (plus the global excludes file, applied relative to `directory`),
+/// and `.gitignore` files are honored whether or not `directory` is inside a git repository.
+/// Shorthand for `FindOptions::new(directory).ignore_files(ignore_files).entry_kind(EntryKind::FilesOnly).recursive(true)`.
+/// Requires the `ignore` feature
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `ignore_files` - Which kinds of ignore files are honored
+///   `process` - Closure that takes a PathBuf to a file (`FnMut(&PathBuf)`), called once per file not ignored
+///       It can return a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+/// 
+/// # Return type
+///   `Result<usize, file_processor::Error>` - The number of files processed
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory or an existing ignore file could not be read
+#[cfg(feature = "ignore")]
+pub fn find_respecting_ignore<R: IntoControlFlow>(directory: impl AsRef<Path>, ignore_files: IgnoreFiles, process: impl FnMut(&PathBuf) -> R, ignore_fail: bool) -> Result<usize, Error> {
+	FindOptions::new(directory).ignore_files(ignore_files).entry_kind(EntryKind::FilesOnly).ignore_fail(ignore_fail).recursive(true).run_with(process)
+}
+
+
+/// Find all files whose size falls in the inclusive range `[min, max]` and run them through the provided function `process`
+/// 
+/// Directories are always skipped. A followed symlinked file is sized by its target.
+/// Shorthand for `FindOptions::new(directory).size(min, max)`
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `min` - The minimum size in bytes, or `None` for no lower bound
+///   `max` - The maximum size in bytes, or `None` for no upper bound
+///   `process` - Closure that takes a PathBuf to a file (`FnMut(&PathBuf)`)
+///       It can return a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned, otherwise unreadable files are skipped
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<usize, file_processor::Error>` - The number of files processed
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory or the metadata of a file could not be read
+pub fn find_by_size_and_then<R: IntoControlFlow>(directory: impl AsRef<Path>, min: Option<u64>, max: Option<u64>, process: impl FnMut(&PathBuf) -> R, ignore_fail: bool, recursive: bool) -> Result<usize, Error> {
+	FindOptions::new(directory).size(min, max).ignore_fail(ignore_fail).recursive(recursive).run_with(process)
+}
+
+
+/// Find all files whose contents start with the bytes `magic` and run them through the provided function `process`
+/// 
+/// This recognizes files by their format rather than their name, like a `.txt` that is really a PNG
+/// (`b"\x89PNG\r\n\x1a\n"`). Only the first `magic.len()` bytes of each file are read, and only once its other
+/// criteria matched. Directories are always skipped. Only `directory` itself is searched, following symlinks and
+/// including hidden files. Shorthand for `FindOptions::new(directory).magic(magic)`
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `magic` - The bytes the files have to start with. Files shorter than it never match
+///   `process` - Closure that takes a PathBuf to a file (`FnMut(&PathBuf)`)
+///       It can return a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned, otherwise unreadable files are skipped
+/// 
+/// # Return type
+///   `Result<usize, file_processor::Error>` - The number of files processed
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - The directory or a file could not be read
+pub fn find_by_magic_and_then<R: IntoControlFlow>(directory: impl AsRef<Path>, magic: &[u8], process: impl FnMut(&PathBuf) -> R, ignore_fail: bool) -> Result<usize, Error> {
+	FindOptions::new(directory).magic(magic).ignore_fail(ignore_fail).run_with(process)
+}
+
+
+/// Find all files whose age (time since their last modification) is within the given bounds and
+/// run them through the provided function `process`
+/// 
+/// Ages are measured against `SystemTime::now()` taken once when the search starts. Files modified
+/// in the future (clock skew) have an age of zero. Directories are always skipped.
+/// Shorthand for `FindOptions::new(directory).age(older_than, newer_than)`
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `older_than` - Only process files last modified more than this long ago, or `None` for no bound
+///   `newer_than` - Only process files last modified less than this long ago, or `None` for no bound
+///   `process` - Closure that takes a PathBuf to a file (`FnMut(&PathBuf)`)
+///       It can return a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned, otherwise unreadable files are skipped
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<usize, file_processor::Error>` - The number of files processed
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory, the metadata of a file or its modification date could not be read
+pub fn find_by_age_and_then<R: IntoControlFlow>(directory: impl AsRef<Path>, older_than: Option<Duration>, newer_than: Option<Duration>, process: impl FnMut(&PathBuf) -> R, ignore_fail: bool, recursive: bool) -> Result<usize, Error> {
+	FindOptions::new(directory).age(older_than, newer_than).ignore_fail(ignore_fail).recursive(recursive).run_with(process)
+}
+
+
+/// Find all entries accepted by `predicate` and run them through the provided function `process`
+/// 
+/// The most general filter, for what the others cannot express (files owned by root, read-only files...).
+/// `predicate` is handed the metadata read while walking (see `FindOptions::run_with_metadata`), following
+/// symlinks, and entries it rejects are not counted. Directories are handed to it too, `Metadata::is_file` tells
+/// them apart. Only `directory` itself is searched, following symlinks and including hidden files
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `predicate` - Closure that takes the path to an entry and its metadata, and returns whether to process it
+///       (`Fn(&Path, &Metadata) -> bool`)
+///   `process` - Closure that takes a PathBuf to an entry (`FnMut(&PathBuf)`)
+///       It can return a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned, otherwise entries without metadata are skipped
+/// 
+/// # Return type
+///   `Result<usize, file_processor::Error>` - The number of entries processed
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - The directory or the metadata of an entry could not be read
+pub fn find_where_and_then<R: IntoControlFlow>(directory: impl AsRef<Path>, predicate: impl Fn(&Path, &std::fs::Metadata) -> bool, mut process: impl FnMut(&PathBuf) -> R, ignore_fail: bool) -> Result<usize, Error> {
+	FindOptions::new(directory).ignore_fail(ignore_fail).run_metadata(&mut |path, metadata| {
+		Ok(if predicate(path, metadata) { Some(process(path).into_control_flow()) } else { None })
+	})
+}
+
+
+/// Find all files provided in `filenames` and run them through the provided function `process`
+/// 
+/// Shorthand for `FindOptions::new(directory).filenames(filenames)`, see `FindOptions` for the other options
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `process` - Closure that takes a PathBuf to a file (`FnMut(&PathBuf)`)
+///       It can return a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<usize, file_processor::Error>` - The number of files processed
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory could not be read (permissions, removed during the search...)
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) that have not been found
+pub fn find_and_then<R: IntoControlFlow>(directory: impl AsRef<Path>, filenames: Vec<String>, process: impl FnMut(&PathBuf) -> R, ignore_fail: bool, recursive: bool) -> Result<usize, Error> {
+	FindOptions::new(directory).filenames(filenames).ignore_fail(ignore_fail).recursive(recursive).run_with(process)
+}
+
+
+/// Find all files provided in `filenames` from async code and await the future `process` returns for each of them
+/// 
+/// Requires the `async` feature. Matches exactly like `find_and_then`, with the directory walked on a background
+/// thread so the executor is not blocked (see `FindOptions::run_async`). Shorthand for
+/// `FindOptions::new(directory).filenames(filenames).run_async(process)`
+/// 
+/// ```
+/// # async fn handler() -> Result<(), file_processor::Error> {
+/// let mut sizes = Vec::new();
+/// 
+/// file_processor::find_and_then_async("./assets", vec![String::from("a.png")], |path| {
+///     let size = std::fs::metadata(&path).map(|metadata| metadata.len());
+///     sizes.push((path, size));
+///     async {}
+/// }, false, true).await?;
+/// # Ok(())
+/// # }
+/// ```
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `process` - Closure that takes a PathBuf to a file and returns a future (`FnMut(PathBuf) -> impl Future`)
+///       The future can output a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<usize, file_processor::Error>` - The number of files processed
+/// 
+/// # Errors
+///   The same as `find_and_then`
+#[cfg(feature = "async")]
+pub async fn find_and_then_async<R: IntoControlFlow, F: std::future::Future<Output = R>>(directory: impl AsRef<Path>, filenames: Vec<String>, process: impl FnMut(PathBuf) -> F, ignore_fail: bool, recursive: bool) -> Result<usize, Error> {
+	FindOptions::new(directory).filenames(filenames).ignore_fail(ignore_fail).recursive(recursive).run_async(process).await
+}
+
+
+/// Find all files provided in `filenames` and run them through the provided function `process` in batches
+/// of up to `batch_size` paths, to pay a fixed cost of `process` (a database transaction) once per batch
+/// 
+/// Batches are delivered during the search, in the order the files were found. The last batch holds the
+/// remaining paths and can be smaller, it is still delivered when the search fails or files are missing.
+/// Only `directory` itself is searched, see `FindOptions` for the other options
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `batch_size` - The number of paths in every batch but the last one
+///   `process` - Closure that takes a batch of paths to files (`FnMut(&[PathBuf])`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+/// 
+/// # Return type
+///   `Result<usize, file_processor::Error>` - The number of files processed
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - The directory could not be read
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) that have not been found
+pub fn find_and_then_batched(directory: impl AsRef<Path>, filenames: Vec<String>, batch_size: NonZeroUsize, mut process: impl FnMut(&[PathBuf]), ignore_fail: bool) -> Result<usize, Error> {
+	let mut batch = Vec::with_capacity(batch_size.get());
+
+	let result = FindOptions::new(directory).filenames(filenames).ignore_fail(ignore_fail).run_with(|path| {
+		batch.push(path.clone());
+
+		if batch.len() == batch_size.get() {
+			process(&batch);
+			batch.clear();
+		}
+	});
+
+	if !batch.is_empty() {
+		process(&batch);
+	}
+
+	result
+}
+
+
+/// Find all files named by the lines of `reader` and run them through the provided function `process`,
+/// to take the list of a pipeline from the standard input (`std::io::stdin().lock()`)
+/// 
+/// Every line is a filename, without its line ending (`\n` or `\r\n`). Empty lines are ignored. The whole list
+/// is read before the search starts. Only `directory` itself is searched, and names are matched like in
+/// `find_and_then`: to look up paths relative to `directory`, as `find .` lists them, read the lines into
+/// `FindOptions::filenames` with `relative_paths` and `recursive` set instead
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `reader` - The list of filenames to be searched, one per line. A name listed twice is looked up once
+///   `process` - Closure that takes a PathBuf to a file (`FnMut(&PathBuf)`)
+///       It can return a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+/// 
+/// # Return type
+///   `Result<usize, file_processor::Error>` - The number of files processed
+/// 
+/// # Errors
+///   `IoError(std::io::Error)` - `reader` could not be read or is not valid UTF-8, or a directory could not be read
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names listed by `reader` that have not been found
+pub fn find_from_reader<R: IntoControlFlow>(directory: impl AsRef<Path>, reader: impl BufRead, process: impl FnMut(&PathBuf) -> R, ignore_fail: bool) -> Result<usize, Error> {
+	let mut filenames = Vec::new();
+
+	for line in reader.lines() {
+		let line = line.map_err(Error::IoError)?;
+
+		if !line.is_empty() {
+			filenames.push(line);
+		}
+	}
+
+	FindOptions::new(directory).filenames(filenames).ignore_fail(ignore_fail).run_with(process)
+}
+
+
+/// The criteria of `find_combined` and `total_size`, of which empty vectors are not used
+#[derive(Debug, Clone, Default)]
+pub struct FindFilter {
+	/// Exact filenames, reported as `MissingFiles` when not found
+	pub names: Vec<String>,
+	/// Extensions, with or without their leading dot and compared ignoring ASCII case
+	pub extensions: Vec<String>,
+	/// Wildcard patterns (see `find_by_glob_and_then`)
+	pub globs: Vec<String>,
+	/// Wildcard patterns of the names never matched, whatever the other criteria (see `FindOptions::exclude`)
+	pub exclude: Vec<String>,
+	/// Whether an entry has to match all of the criteria instead of any of them
+	pub match_all: bool,
+}
+
+impl FindFilter {
+	/// A search of `directory` with these criteria
+	fn into_options(self, directory: impl AsRef<Path>) -> FindOptions {
+		let mut options = FindOptions::new(directory).match_any(!self.match_all);
+
+		if !self.names.is_empty() {
+			options = options.filenames(self.names);
+		}
+		if !self.extensions.is_empty() {
+			options = options.extensions(self.extensions);
+		}
+		if !self.globs.is_empty() {
+			options = options.globs(self.globs);
+		}
+		if !self.exclude.is_empty() {
+			options = options.exclude(self.exclude);
+		}
+
+		options
+	}
+}
+
+
+/// Find all files matching any (or all, with `match_all`) of the criteria of `filter` and run them through
+/// the provided function `process`
+/// 
+/// Files matched by several criteria are processed once. Only `directory` itself is searched, following symlinks
+/// and including hidden files. Shorthand for `FindOptions::new(directory)` with `filenames`, `extensions`, `globs`
+/// and `match_any(!filter.match_all)`, which can also search recursively
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filter` - The criteria to match
+///   `process` - Closure that takes a PathBuf to a file (`FnMut(&PathBuf)`)
+///       It can return a `bool` or a `ControlFlow<()>` to stop the search early (see `IntoControlFlow`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+/// 
+/// # Return type
+///   `Result<usize, file_processor::Error>` - The number of files processed
+/// 
+/// # Errors
+///   `InvalidPattern(String)` - A pattern could not be compiled (an unclosed `[`)
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - The directory could not be read
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The requested `names` that have not been found
+pub fn find_combined<R: IntoControlFlow>(directory: impl AsRef<Path>, filter: FindFilter, process: impl FnMut(&PathBuf) -> R, ignore_fail: bool) -> Result<usize, Error> {
+	filter.into_options(directory).ignore_fail(ignore_fail).run_with(process)
+}
+
+
+/// Sum the sizes of all files matching any (or all, with `match_all`) of the criteria of `filter`, like before
+/// archiving them
+/// 
+/// Directories are never counted, neither their own size nor, unless `recursive` is set, their contents.
+/// Symlinks are followed, so a linked file counts with the size of its target, once per link.
+/// An empty `filter` matches every file
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filter` - The criteria to match (see `find_combined`)
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<u64, file_processor::Error>` - The total size in bytes
+/// 
+/// # Errors
+///   `InvalidPattern(String)` - A pattern could not be compiled (an unclosed `[`)
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory or the metadata of a file could not be read
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The requested `names` that have not been found
+pub fn total_size(directory: impl AsRef<Path>, filter: FindFilter, recursive: bool) -> Result<u64, Error> {
+	let mut total = 0;
+
+	filter.into_options(directory).entry_kind(EntryKind::FilesOnly).recursive(recursive)
+		.run_with_metadata(|_, metadata| total += metadata.len())?;
+
+	Ok(total)
+}
+
+
+/// The failures collected by `find_and_then_collecting_errors`, each with the path it happened on
+pub type EntryErrors = Vec<(PathBuf, Error)>;
+
+
+/// The outcome of a batch that goes on after every failure, like `find_and_then_collecting_errors`
+/// or `find_and_then_pooled`
+#[derive(Debug)]
+pub struct ProcessReport<T> {
+	/// What `process` returned for every entry it succeeded on
+	pub processed: Vec<T>,
+	/// The number of entries examined but not processed, because they did not match the search or could not
+	/// be examined (those failures are in `errors`)
+	pub skipped: usize,
+	/// Every failure limited to one entry, with its path
+	pub errors: EntryErrors,
+	/// The names of the requested files that have not been found
+	pub missing: Vec<String>,
+}
+
+impl<T> ProcessReport<T> {
+	/// Whether every entry was processed without failure and every requested file was found
+	pub fn is_success(&self) -> bool {
+		self.errors.is_empty() && self.missing.is_empty()
+	}
+
+	/// What `process` returned for every entry, or the first failure: the first of `errors`, then
+	/// `MissingFiles(Vec<String>)` with the missing names
+	pub fn into_result(self) -> Result<Vec<T>, Error> {
+		if let Some((_, err)) = self.errors.into_iter().next() {
+			return Err(err);
+		}
+
+		if !self.missing.is_empty() {
+			return Err(Error::MissingFiles(self.missing));
+		}
+
+		Ok(self.processed)
+	}
+}
+
+
+/// Callback of `FindOptions::run_metadata`, returning `None` to skip an entry
+type MetadataCallback<'a> = dyn FnMut(&PathBuf, &std::fs::Metadata) -> Result<Option<ControlFlow<()>>, Error> + 'a;
+
+
+/// Find all files provided in `filenames` and run them through the provided function `process`, going on
+/// after every failure and returning them all instead
+/// 
+/// One unreadable file does not keep the others from being processed, and no failure is silently dropped.
+/// Shorthand for `FindOptions::new(directory).filenames(filenames).run_collecting_errors(process)`
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `process` - Closure that takes a PathBuf to a file and returns what it made of it (`FnMut(&PathBuf) -> Result<T, Error>`)
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<ProcessReport<T>, file_processor::Error>` - What `process` returned for every file it succeeded on,
+///       every failure with its path, and the names of the requested files (`filenames`) that have not been found
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `NullDirectory` - The provided directory is null
+pub fn find_and_then_collecting_errors<T>(directory: impl AsRef<Path>, filenames: Vec<String>, process: impl FnMut(&PathBuf) -> Result<T, Error>, recursive: bool) -> Result<ProcessReport<T>, Error> {
+	FindOptions::new(directory).filenames(filenames).recursive(recursive).run_collecting_errors(process)
+}
+
+
+/// Find all files provided in `filenames` and return their paths
+/// 
+/// Matches exactly like `find_and_then`, without running a processing function
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<Vec<PathBuf>, file_processor::Error>` - The matched paths in the order they were found
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory could not be read (permissions, removed during the search...)
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) that have not been found
+pub fn find_matching(directory: impl AsRef<Path>, filenames: Vec<String>, ignore_fail: bool, recursive: bool) -> Result<Vec<PathBuf>, Error> {
+	let mut paths = Vec::new();
+
+	find_and_then(directory, filenames, |path| paths.push(path.clone()), ignore_fail, recursive)?;
+
+	Ok(paths)
+}
+
+
+/// Find all files provided in `filenames` and map each requested name to the absolute path it was found at,
+/// to record where every file came from (reproducibility logs, cache keys)
+/// 
+/// Matches exactly like `find_matching`. Paths are made absolute against the working directory but not
+/// canonical, so symlinks are kept. When several files share a requested name (in different subdirectories),
+/// the first one found is kept
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<HashMap<String, PathBuf>, file_processor::Error>` - The absolute path of every file, keyed by its name
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory could not be read, or the working directory could not be read
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) that have not been found
+pub fn find_manifest(directory: impl AsRef<Path>, filenames: Vec<String>, ignore_fail: bool, recursive: bool) -> Result<HashMap<String, PathBuf>, Error> {
+	let mut found = HashMap::new();
+
+	find_and_then(directory, filenames, |path| {
+		if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+			found.entry(name.to_string()).or_insert_with(|| path.clone());
+		}
+	}, ignore_fail, recursive)?;
+
+	absolute_manifest(found)
+}
+
+
+/// Make every path of `manifest` absolute, against the working directory
+fn absolute_manifest(manifest: HashMap<String, PathBuf>) -> Result<HashMap<String, PathBuf>, Error> {
+	let working = std::env::current_dir().map_err(Error::IoError)?;
+
+	Ok(manifest.into_iter().map(|(name, path)| {
+		let path = if path.is_absolute() { path } else { working.join(path) };
+		(name, path)
+	}).collect())
+}
+
+
+/// Find all files provided in `filenames` and return the paths found along with the names that were not
+/// 
+/// Matches exactly like `find_matching`, but missing files are not an error, so a program can report partial
+/// results ("loaded 8 of 10 configs, missing ..."). Any other failure still is
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<(Vec<PathBuf>, Vec<String>), file_processor::Error>` - The matched paths in the order they were found,
+///       and the names of the requested files (`filenames`) that have not been found
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory could not be read (permissions, removed during the search...)
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+pub fn partition_found(directory: impl AsRef<Path>, filenames: Vec<String>, recursive: bool) -> Result<(Vec<PathBuf>, Vec<String>), Error> {
+	let mut paths = Vec::new();
+
+	let missing = match find_and_then(directory, filenames, |path| paths.push(path.clone()), false, recursive) {
+		Ok(_) => Vec::new(),
+		Err(Error::MissingFiles(missing)) => missing,
+		Err(err) => return Err(err),
+	};
+
+	Ok((paths, missing))
+}
+
+
+/// Check that all files provided in `filenames` exist, to fail fast when a program is missing some of them
+/// 
+/// Matches exactly like `find_and_then`, without running a processing function. Every missing name is reported,
+/// not only the first one
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames required. A name requested twice is looked up once
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<(), file_processor::Error>` - `Ok` when every file has been found
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory could not be read (permissions, removed during the search...)
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names of the required files (`filenames`) that have not been found
+pub fn require_files(directory: impl AsRef<Path>, filenames: Vec<String>, recursive: bool) -> Result<(), Error> {
+	find_and_then(directory, filenames, |_| (), false, recursive)?;
+
+	Ok(())
+}
+
+
+/// Find all files provided in `filenames` in a list of search paths, like `PATH`, where the first directory
+/// holding a file wins
+/// 
+/// The directories are searched in order, each one only for the files not found in the previous ones.
+/// Directories that do not exist, or are files, hold none of the files and are skipped. Only the directories themselves are
+/// searched, following symlinks and including hidden files
+/// 
+/// # Variables
+///   `directories` - The directories to search, from the highest to the lowest priority
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+/// 
+/// # Return type
+///   `Result<Vec<PathBuf>, file_processor::Error>` - The path where each file was found, in the order of `filenames`
+/// 
+/// # Errors
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - A provided directory is null
+///   `IoError(std::io::Error)` - A directory could not be read (permissions, removed during the search...)
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) found in none of the directories
+pub fn find_in_dirs(directories: Vec<PathBuf>, filenames: Vec<String>, ignore_fail: bool) -> Result<Vec<PathBuf>, Error> {
+	let mut requested = std::collections::HashSet::new();
+	let filenames: Vec<String> = filenames.into_iter().filter(|name| requested.insert(name.clone())).collect();
+
+	Ok(find_in_dirs_manifest_ordered(directories, filenames, ignore_fail)?.into_iter().map(|(_, path)| path).collect())
+}
+
+
+/// Find all files provided in `filenames` in a list of search paths like `find_in_dirs`, and map each requested
+/// name to the absolute path where it was found, which tells the directory that won
+/// 
+/// Paths are made absolute against the working directory but not canonical, so they keep the searched
+/// directory as their parent
+/// 
+/// # Variables
+///   `directories` - The directories to search, from the highest to the lowest priority
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+/// 
+/// # Return type
+///   `Result<HashMap<String, PathBuf>, file_processor::Error>` - The absolute path of every file, keyed by its name
+/// 
+/// # Errors
+///   The same as `find_in_dirs`, or `IoError(std::io::Error)` when the working directory could not be read
+pub fn find_in_dirs_manifest(directories: Vec<PathBuf>, filenames: Vec<String>, ignore_fail: bool) -> Result<HashMap<String, PathBuf>, Error> {
+	let mut requested = std::collections::HashSet::new();
+	let filenames: Vec<String> = filenames.into_iter().filter(|name| requested.insert(name.clone())).collect();
+
+	absolute_manifest(find_in_dirs_manifest_ordered(directories, filenames, ignore_fail)?.into_iter().collect())
+}
+
+
+/// The path where each of the distinct `filenames` was found by `find_in_dirs`, in the order of `filenames`
+fn find_in_dirs_manifest_ordered(directories: Vec<PathBuf>, filenames: Vec<String>, ignore_fail: bool) -> Result<Vec<(String, PathBuf)>, Error> {
+	let mut found: HashMap<String, PathBuf> = HashMap::new();
+
+	for directory in &directories {
+		let remaining: Vec<String> = filenames.iter().filter(|name| !found.contains_key(*name)).cloned().collect();
+		if remaining.is_empty() {
+			break;
+		}
+
+		if !directory.is_dir() {
+			continue;
+		}
+
+		let searched = FindOptions::new(directory).filenames(remaining).ignore_fail(ignore_fail).run_with(|path| {
+			if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+				found.entry(name.to_string()).or_insert_with(|| path.clone());
+			}
+		});
+
+		match searched {
+			Ok(_) | Err(Error::MissingFiles(_)) => (),
+			Err(err) => return Err(err),
+		}
+	}
+
+	let mut paths = Vec::new();
+	let mut missing = Vec::new();
+
+	for name in filenames {
+		match found.remove(&name) {
+			Some(path) => paths.push((name, path)),
+			None => missing.push(name),
+		}
+	}
+
+	if !missing.is_empty() {
+		return Err(Error::MissingFiles(missing));
+	}
+
+	Ok(paths)
+}
+
+
+/// Find all files provided in `filenames` in every directory matching `dir_glob`, like `projects/*/assets`
+/// 
+/// The glob selects the search roots themselves, each component of the path being matched like the patterns of
+/// `find_by_glob_and_then` against directory names (following symlinks). Every matching root is searched,
+/// exactly like `find_and_then`, and a file only has to be found in one of them. Only the roots themselves are
+/// searched, following symlinks and including hidden files
+/// 
+/// # Variables
+///   `dir_glob` - The path of the roots, whose components can hold wildcards (`*`, `?`, `[abc]`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+/// 
+/// # Return type
+///   `Result<Vec<(PathBuf, PathBuf)>, file_processor::Error>` - The root each file was found in and its path,
+///       with the roots sorted by path
+/// 
+/// # Errors
+///   `InvalidPattern(String)` - A component of `dir_glob` could not be compiled (an unclosed `[`)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `IoError(std::io::Error)` - A directory could not be read (permissions, removed during the search...)
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+///   `MissingFiles(Vec<String>)` - The names of the requested files (`filenames`) found in none of the roots
+pub fn find_in_glob_dirs(dir_glob: &str, filenames: Vec<String>, ignore_fail: bool) -> Result<Vec<(PathBuf, PathBuf)>, Error> {
+	let mut found = Vec::new();
+	let mut missing: Option<Vec<String>> = None;
+
+	for root in glob::expand_dirs(dir_glob, ignore_fail)? {
+		let searched = FindOptions::new(&root).filenames(filenames.clone()).ignore_fail(ignore_fail).run_with(|path| {
+			found.push((root.clone(), path.clone()));
+		});
+
+		// A name is only missing when no root has it
+		let still_missing = match searched {
+			Ok(_) => Vec::new(),
+			Err(Error::MissingFiles(names)) => names,
+			Err(err) => return Err(err),
+		};
+
+		missing = Some(match missing {
+			Some(missing) => missing.into_iter().filter(|name| still_missing.contains(name)).collect(),
+			None => still_missing,
+		});
+	}
+
+	let missing = missing.unwrap_or_else(|| {
+		let mut requested = std::collections::HashSet::new();
+		filenames.into_iter().filter(|name| requested.insert(name.clone())).collect()
+	});
+
+	if !missing.is_empty() {
+		return Err(Error::MissingFiles(missing));
+	}
+
+	Ok(found)
+}
+
+/// Find all files with the extensions provided in `extensions` and return their paths
+/// 
+/// Matches exactly like `find_by_extension_and_then`, without running a processing function
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `extensions` - A slice of all the extensions to be filtered (`jpg` or `.jpg`), compared ignoring ASCII case
+///       It is only borrowed, so the same list (`&[&str]`, `&[String]`...) can be reused for several searches
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<Vec<PathBuf>, file_processor::Error>` - The matched paths in the order they were found
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory could not be read (permissions, removed during the search...)
+pub fn find_by_extension(directory: impl AsRef<Path>, extensions: &[impl AsRef<str>], ignore_fail: bool, recursive: bool) -> Result<Vec<PathBuf>, Error> {
+	let mut paths = Vec::new();
+
+	find_by_extension_and_then(directory, extensions, |path| paths.push(path.clone()), ignore_fail, recursive)?;
+
+	Ok(paths)
+}
+
+
+/// Find all files with the extensions provided in `extensions` and return their paths, each paired with
+/// the extension that matched it
+/// 
+/// Matches exactly like `find_by_extension_and_then`. The extension is returned as it was provided (`.JPG` for
+/// `photo.jpg` when `.JPG` was requested), so results can be grouped by the entries of `extensions`. When a file
+/// matches several of them, the longest one is returned (`archive.tar.gz` is paired with `tar.gz` rather than `gz`),
+/// and the first one provided among extensions of the same length (`jpg` and `.JPG`)
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `extensions` - A slice of all the extensions to be filtered (`jpg` or `.jpg`), compared ignoring ASCII case
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<Vec<(PathBuf, String)>, file_processor::Error>` - The matched paths and their extension in the order
+///       they were found
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory could not be read (permissions, removed during the search...)
+pub fn find_by_extension_matched(directory: impl AsRef<Path>, extensions: &[impl AsRef<str>], ignore_fail: bool, recursive: bool) -> Result<Vec<(PathBuf, String)>, Error> {
+	let extensions: Vec<String> = extensions.iter().map(|ext| ext.as_ref().to_string()).collect();
+	let mut matched = Vec::new();
+
+	FindOptions::new(directory).extensions(extensions.clone()).ignore_fail(ignore_fail).recursive(recursive).run_with(|path| {
+		if let Some(ext) = search::matching_extension(path, &extensions, false) {
+			matched.push((path.clone(), ext.to_string()));
+		}
+	})?;
+
+	Ok(matched)
+}
+
+
+/// Find all files in `directory` and group them by their extension, for reports
+/// 
+/// Extensions are lowercased and stored without their leading dot, so `a.JPG` and `b.jpg` share the key `jpg`.
+/// Files without an extension (`Makefile`, or dotfiles like `.gitignore`) are grouped under the empty key `""`.
+/// Directories are not grouped, but they are descended into when `recursive` is set
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<HashMap<String, Vec<PathBuf>>, file_processor::Error>` - The paths of the files of every extension,
+///       in the order they were found
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory could not be read (permissions, removed during the search...)
+pub fn group_by_extension(directory: impl AsRef<Path>, recursive: bool) -> Result<HashMap<String, Vec<PathBuf>>, Error> {
+	let mut groups: HashMap<String, Vec<PathBuf>> = HashMap::new();
+
+	FindOptions::new(directory).entry_kind(EntryKind::FilesOnly).recursive(recursive).run_with(|path| {
+		let ext = path.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+
+		groups.entry(ext).or_default().push(path.clone());
+	})?;
+
+	Ok(groups)
+}
+
+
+/// Find all files with the extensions provided in `extensions` and count their lines, for code metrics
+/// 
+/// Lines are counted by their `\n`, so `\r\n` endings count once, and a last line without a trailing newline
+/// counts too. Files are read in chunks, so they are never loaded in memory as a whole. Binary files, which have
+/// a NUL byte in their first chunk, are skipped. `directory` and its subdirectories are searched, following
+/// symlinks and including hidden files
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `extensions` - A slice of all the extensions to be filtered (`rs` or `.rs`), compared ignoring ASCII case
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned, otherwise unreadable files are skipped
+/// 
+/// # Return type
+///   `Result<Vec<(PathBuf, usize)>, file_processor::Error>` - The path and line count of every text file,
+///       in the order they were found
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory or a file could not be read
+pub fn find_and_count_lines(directory: impl AsRef<Path>, extensions: &[impl AsRef<str>], ignore_fail: bool) -> Result<Vec<(PathBuf, usize)>, Error> {
+	let extensions = extensions.iter().map(|ext| ext.as_ref().to_string()).collect();
+	let mut counts = Vec::new();
+
+	FindOptions::new(directory).extensions(extensions).entry_kind(EntryKind::FilesOnly).ignore_fail(ignore_fail).recursive(true).run(&mut |path| {
+		match count_lines(path) {
+			Ok(Some(lines)) => counts.push((path.clone(), lines)),
+			Ok(None) => (),
+			Err(err) => if !ignore_fail { return Err(Error::IoError(err)) },
+		}
+
+		Ok(ControlFlow::Continue(()))
+	})?;
+
+	Ok(counts)
+}
+
+
+/// How much of a file is read at once by `count_lines`, the first chunk being checked for NUL bytes
+const LINE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The number of lines of the text file at `path`, or `None` when it is a binary file
+fn count_lines(path: &Path) -> std::io::Result<Option<usize>> {
+	let mut file = std::fs::File::open(path)?;
+	let mut buffer = vec![0; LINE_CHUNK_SIZE];
+	let mut read = 0;
+	let mut lines = 0;
+	let mut last = b'\n';
+
+	loop {
+		let chunk = match file.read(&mut buffer) {
+			Ok(0) => break,
+			Ok(n) => &buffer[..n],
+			Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+			Err(err) => return Err(err),
+		};
+
+		if read < LINE_CHUNK_SIZE && chunk[..chunk.len().min(LINE_CHUNK_SIZE - read)].contains(&0) {
+			return Ok(None);
+		}
+
+		read += chunk.len();
+		lines += chunk.iter().filter(|&&b| b == b'\n').count();
+		last = chunk[chunk.len() - 1];
+	}
+
+	if last != b'\n' {
+		lines += 1;
+	}
+
+	Ok(Some(lines))
+}
+
+
+/// List the files a `*_and_then` search would process, without processing them
+/// 
+/// `find` receives a recording closure to pass as the `process` argument of the search, so the
+/// selection is exactly the one the real run would make. Searches built with `FindOptions` can set
+/// `FindOptions::dry_run` or call `FindOptions::plan` instead:
+/// 
+/// ```
+/// let planned = file_processor::dry_run(|record| {
+///     file_processor::find_by_size_and_then(".", Some(50_000_000), None, record, false, true)
+/// });
+/// ```
+/// 
+/// # Variables
+///   `find` - Closure running the search with the given recording closure as its `process`
+/// 
+/// # Return type
+///   `Result<Vec<PathBuf>, file_processor::Error>` - The paths the search would have processed, in order.
+///       Any error of the search is returned as is
+pub fn dry_run(find: impl FnOnce(&mut dyn FnMut(&PathBuf)) -> Result<usize, Error>) -> Result<Vec<PathBuf>, Error> {
+	let mut paths = Vec::new();
+
+	find(&mut |path| paths.push(path.clone()))?;
+
+	Ok(paths)
+}
+
+
+/// Run `process` on every path of `paths`, along with its index, using `workers` scoped threads
+/// 
+/// Each worker pulls the next unprocessed path from a shared counter, so slow files do not hold up the rest
+fn process_parallel(paths: &[PathBuf], workers: usize, process: &(dyn Fn(usize, &PathBuf) + Sync)) {
+	let next = AtomicUsize::new(0);
+
+	std::thread::scope(|scope| {
+		for _ in 0..workers.min(paths.len()) {
+			scope.spawn(|| loop {
+				let index = next.fetch_add(1, Ordering::Relaxed);
+				match paths.get(index) {
+					Some(path) => process(index, path),
+					None => break,
+				}
+			});
+		}
+	});
+}
+
+
+/// Find all files with the extensions provided in `extensions` and run them through `process` in parallel
+/// 
+/// The directory is scanned first, exactly like `find_by_extension`, and the matched files are then
+/// processed by one thread per available CPU. Files are processed concurrently and in a nondeterministic
+/// order, so `process` must be `Fn + Sync + Send`; use atomics, a `Mutex` or a channel to collect results.
+/// If `process` panics the panic is propagated once every thread has stopped
+/// 
+/// The threads are scoped threads of the standard library (`std::thread::scope`) each taking the next file
+/// in turn, rather than a `rayon` pool: a single scan handing out whole files does not need work stealing
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `extensions` - A slice of all the extensions to be filtered (`jpg` or `.jpg`), compared ignoring ASCII case
+///       It is only borrowed, so the same list (`&[&str]`, `&[String]`...) can be reused for several searches
+///   `process` - Closure that takes a PathBuf to a file (`Fn(&PathBuf) + Sync + Send`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
+/// 
+/// # Return type
+///   `Result<usize, file_processor::Error>` - The number of files processed. No file is processed if the scan fails
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory could not be read (permissions, removed during the search...)
+pub fn par_find_by_extension_and_then(directory: impl AsRef<Path>, extensions: &[impl AsRef<str>], process: impl Fn(&PathBuf) + Sync + Send, ignore_fail: bool, recursive: bool) -> Result<usize, Error> {
+	let paths = find_by_extension(directory, extensions, ignore_fail, recursive)?;
+
+	let workers = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+	process_parallel(&paths, workers, &|_, path| process(path));
+
+	Ok(paths.len())
+}
+
+
+/// Find all files provided in `filenames` and run them through `process` on a pool of `workers` threads,
+/// going on after every failure and returning them all instead
+/// 
+/// The directory is scanned first, exactly like `find_and_then`, and the matched files are then shared out
+/// between the workers, which are the only threads used (no global pool). This bounds how many files are
+/// processed at once, which is the throughput knob for IO-bound work like uploads. Files are processed
+/// concurrently and in a nondeterministic order, but the results keep the order the files were found in.
+/// If `process` panics the panic is propagated once every thread has stopped
+/// 
+/// # Variables
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be searched. A name requested twice is looked up once
+///   `process` - Closure that takes a PathBuf to a file and returns what it made of it (`Fn(&PathBuf) -> Result<T, Error> + Sync`)
+///   `workers` - The number of threads processing files. It is a `NonZeroUsize`, so an empty pool that would
+///       never process anything has to be handled where the count is made (`NonZeroUsize::new` returns `None`)
+///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
+///       If it is unset(`false`) a `file_processor::Error` will be returned
+/// 
+/// # Return type
+///   `Result<ProcessReport<T>, file_processor::Error>` - What `process` returned for every file it succeeded on,
+///       every failure of `process` with its path, and the names of the requested files (`filenames`) that have
+///       not been found
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - A directory could not be read (permissions, removed during the search...)
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+pub fn find_and_then_pooled<T: Send>(directory: impl AsRef<Path>, filenames: Vec<String>, process: impl Fn(&PathBuf) -> Result<T, Error> + Sync, workers: NonZeroUsize, ignore_fail: bool) -> Result<ProcessReport<T>, Error> {
+	let mut paths = Vec::new();
+	let mut examined = 0;
+
+	let searched = FindOptions::new(directory).filenames(filenames).ignore_fail(ignore_fail).run_with_progress(|path| paths.push(path.clone()), |count, _| examined = count);
+
+	let missing = match searched {
+		Ok(_) => Vec::new(),
+		Err(Error::MissingFiles(missing)) => missing,
+		Err(err) => return Err(err),
+	};
+
+	// One slot per file, so the results can be put back in order once every worker is done
+	let slots: Vec<std::sync::Mutex<Option<Result<T, Error>>>> = paths.iter().map(|_| std::sync::Mutex::new(None)).collect();
+	process_parallel(&paths, workers.get(), &|index, path| {
+		let result = process(path);
+		*slots[index].lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(result);
+	});
+
+	let mut report = ProcessReport{ processed: Vec::new(), skipped: examined - paths.len(), errors: Vec::new(), missing };
+
+	for (path, slot) in paths.into_iter().zip(slots) {
+		match slot.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner()) {
+			Some(Ok(result)) => report.processed.push(result),
+			Some(Err(err)) => report.errors.push((path, err)),
+			None => (),
+		}
+	}
+
+	Ok(report)
+}
+
+
+/// The kinds of filesystem entries `check_file` can expect
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+	/// A regular file, or a symlink to one
+	File,
+	/// A directory, or a symlink to one
+	Dir,
+	/// A symbolic link itself, whatever it points to
+	Symlink,
+}
+
+
+/// Check that `path` exists and is an entry of the `expect`ed kind
+/// 
+/// Symlinks are followed when expecting a `File` or a `Dir`, so a dangling symlink does not exist for them
+/// 
+/// # Variables
+///   `path` - The entry to check (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `expect` - The kind of entry `path` should be
+/// 
+/// # Return type
+///   `Result<(), file_processor::Error>` - `Ok` when `path` exists with the expected kind
+/// 
+/// # Errors
+///   `FileDoesNotExist(std::path::PathBuf)` - Nothing exists at `path`
+///   `UnexpectedKind(std::path::PathBuf, FileKind)` - `path` exists but is not of the expected kind
+///   `IoError(std::io::Error)` - The metadata of `path` could not be read (permissions...)
+pub fn check_file(path: impl AsRef<Path>, expect: FileKind) -> Result<(), Error> {
+	let path = path.as_ref();
+	let metadata = match expect {
+		FileKind::Symlink => std::fs::symlink_metadata(path),
+		FileKind::File | FileKind::Dir => std::fs::metadata(path),
+	};
+
+	let metadata = match metadata {
+		Ok(metadata) => metadata,
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(Error::FileDoesNotExist(path.to_path_buf())),
+		Err(e) => return Err(Error::IoError(e)),
+	};
+
+	let matches = match expect {
+		FileKind::File => metadata.is_file(),
+		FileKind::Dir => metadata.is_dir(),
+		FileKind::Symlink => metadata.file_type().is_symlink(),
+	};
+
+	if matches {
+		Ok(())
+	} else {
+		Err(Error::UnexpectedKind(path.to_path_buf(), expect))
+	}
+}
+
+
+/// The entries selected by `options` along with their modification date, in the order they were found
+/// 
+/// Missing `filenames` are not an error, and entries without a date are left out
+fn poll_dates(options: &FindOptions) -> Result<Vec<(PathBuf, std::time::SystemTime)>, Error> {
+	let mut dates = Vec::new();
+
+	let found = options.run_with_metadata(|path, metadata| {
+		if let Ok(date) = metadata.modified() {
+			dates.push((path.to_path_buf(), date));
+		}
+	});
+
+	match found {
+		Ok(_) | Err(Error::MissingFiles(_)) => Ok(dates),
+		Err(err) => Err(err),
+	}
+}
+
+
+/// Watch the directories searched by `options` with `notifications`: `directory`, and its subdirectories
+/// when `recursive` is set
+#[cfg(all(target_os = "linux", feature = "watch"))]
+fn watch_directories(notifications: &inotify::Inotify, options: &FindOptions) -> Result<(), Error> {
+	notifications.watch(&options.directory)?;
+
+	if options.recursive {
+		let directories = FindOptions::new(&options.directory).entry_kind(EntryKind::DirsOnly).recursive(true).max_depth(options.max_depth).include_hidden(options.include_hidden).symlinks(options.symlinks).ignore_fail(true);
+
+		directories.run_with(|directory| {
+			// A subdirectory removed since it was found is not searched anymore anyway
+			let _ = notifications.watch(directory);
+		})?;
+	}
+
+	Ok(())
+}
+
+
+/// What happened to an entry reported by `FindOptions::watch_events`
+#[cfg(all(target_os = "linux", feature = "watch"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchEvent {
+	/// The entry was not selected by the previous search (new, renamed into place or newly matching)
+	Created,
+	/// The modification date of the entry changed
+	Modified,
+	/// The entry is not selected anymore (removed, renamed away or not matching anymore)
+	Removed,
+}
+
 
-#[macro_use]
-extern crate load_file;
+/// A watcher of changed files on a background thread (see `FindOptions::watch` and `FindOptions::watch_events`)
+/// 
+/// Dropping the handle stops the watcher without waiting for its thread, which at worst finishes its
+/// current poll first
+#[derive(Debug)]
+pub struct WatchHandle {
+	stopped: CancellationToken,
+	thread: Option<std::thread::JoinHandle<Result<(), Error>>>,
+	/// Wakes a thread waiting for notifications
+	#[cfg(all(target_os = "linux", feature = "watch"))]
+	wake: Option<Arc<inotify::Wake>>,
+}
+
+impl WatchHandle {
+	/// Whether the watcher is still polling, which it stops doing after an error
+	pub fn is_running(&self) -> bool {
+		self.thread.as_ref().is_some_and(|thread| !thread.is_finished())
+	}
 
-use std::path::{PathBuf};
+	/// Stop the watcher and wait for its thread, which at worst finishes its current poll first
+	/// 
+	/// If the processing function panicked the panic is propagated
+	/// 
+	/// # Errors
+	///   The error of the poll that stopped the watcher, if it stopped on its own
+	pub fn stop(mut self) -> Result<(), Error> {
+		self.signal_stop();
 
+		match self.thread.take().map(|thread| thread.join()) {
+			Some(Ok(result)) => result,
+			Some(Err(panic)) => std::panic::resume_unwind(panic),
+			None => Ok(()),
+		}
+	}
 
+	fn signal_stop(&self) {
+		if let Some(thread) = &self.thread {
+			self.stopped.cancel();
+			thread.thread().unpark();
 
-/// Find all files provided in `filenames`, run them through the provided function `process`
-/// and then load the files provided by the function as a byte vector (binary format)
+			#[cfg(all(target_os = "linux", feature = "watch"))]
+			if let Some(wake) = &self.wake {
+				wake.wake();
+			}
+		}
+	}
+}
+
+impl Drop for WatchHandle {
+	fn drop(&mut self) {
+		self.signal_stop();
+	}
+}
+
+
+/// Watch the files provided in `filenames`, calling `process` on each one whose modification date changes
+/// 
+/// Only `directory` itself is polled, every `poll_interval`, on a background thread. Shorthand for
+/// `FindOptions::new(directory).filenames(filenames).watch(process, poll_interval)`, see `FindOptions::watch`
+/// for the details and `FindOptions` for the other options (like `recursive`). This reloads an asset when it
+/// is saved:
+/// 
+/// ```no_run
+/// use std::time::Duration;
+/// 
+/// let watcher = file_processor::watch_and_then("./assets", vec![String::from("style.css")], |path| {
+///     println!("reloading {}", path.display());
+/// }, Duration::from_millis(500)).unwrap();
+/// 
+/// // ...
+/// 
+/// watcher.stop().unwrap();
+/// ```
 /// 
 /// # Variables
-///   `directory` - The directory from which to start the search
-///   `filenames` - A vector of all the filenames to be searched
-///   `process` - Function that takes a PathBuf to a file and returns a PathBuf to the new file created (`fn(&PathBuf)->PathBuf`)
-///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
-///       If it is unset(`false`) a `file_processor::Error` will be returned
+///   `directory` - The directory to watch (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be watched. They do not have to exist yet
+///   `process` - Closure that takes a PathBuf to a changed file (`FnMut(&PathBuf) + Send`)
+///   `poll_interval` - The time waited between two polls
 /// 
 /// # Return type
-///   `Result<&[u8], file_processor::Error>`
+///   `Result<WatchHandle, file_processor::Error>` - The running watcher, stopped when dropped
 /// 
 /// # Errors
 ///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
 ///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
 ///   `NullDirectory` - The provided directory is null
-///   `InvalidUnicodeData` - A file has invalid characters in its extension
-///   `MissingFiles(Vec<usize>)` - Indicates which files from the requested ones (`filenames`) have not been found
-pub fn find_and_then_and_load(directory: PathBuf, filenames: Vec<String>, process: fn(&PathBuf)->PathBuf, ignore_fail: bool) -> Result<Vec<&'static [u8]>, Error> {
-	let mut count = 0;
-
-	let mut indexes: Vec<usize> = (0..filenames.len()).collect();
-
-	let mut binaries: Vec<_> = Vec::new();
-
-	match directory.to_str() {
-		Some(dir) => {
-			if !directory.exists() {
-				return Err(Error::DirectoryDoesNotExist(directory.clone()));
-			}
-
-			for entry in std::fs::read_dir(dir).unwrap() {
-				match entry {
-					Ok(e) => {
-						match e.file_name().to_str() {
-							Some(name) => {
-								for (i, s) in filenames.iter().enumerate() {
-									if s == &String::from(name) {
-										binaries.push(load_bytes!(process(&e.path()).to_str().expect("Return path is incorrect")));
-										count += 1;
-										indexes.remove(i);
-										break;
-									}
-								}
-							},
-
-							None => if !ignore_fail { return Err(Error::InvalidUnicodeData) }
-						}
+///   `IoError(std::io::Error)` - The directory or the metadata of a file could not be read
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+pub fn watch_and_then(directory: impl AsRef<Path>, filenames: Vec<String>, process: impl FnMut(&PathBuf) + Send + 'static, poll_interval: Duration) -> Result<WatchHandle, Error> {
+	FindOptions::new(directory).filenames(filenames).watch(process, poll_interval)
+}
 
-					},
-					_ => if !ignore_fail { return Err(Error::CouldNotOpenEntry) },
-				}
-			}
 
-			if count == filenames.len() {
-				Ok(binaries)
-			} else {
-				Err(Error::MissingFiles(indexes.clone()))
-			}
-		},
-		None => Err(Error::NullDirectory),
-	}
+/// Watch the files provided in `filenames` through the change notifications of the kernel, calling `process`
+/// on each one created, modified or removed
+/// 
+/// Requires the `watch` feature, and is only available on Linux. Only `directory` itself is watched.
+/// Shorthand for `FindOptions::new(directory).filenames(filenames).watch_events(process)`, see
+/// `FindOptions::watch_events` for the details and `FindOptions` for the other options (like `extensions`)
+/// 
+/// # Variables
+///   `directory` - The directory to watch (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `filenames` - A vector of all the filenames to be watched. They do not have to exist yet
+///   `process` - Closure that takes a PathBuf to a changed file and what happened to it (`FnMut(&PathBuf, WatchEvent) + Send`)
+/// 
+/// # Return type
+///   `Result<WatchHandle, file_processor::Error>` - The running watcher, stopped when dropped
+/// 
+/// # Errors
+///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
+///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
+///   `NullDirectory` - The provided directory is null
+///   `IoError(std::io::Error)` - The directory or the metadata of a file could not be read, or the notifications
+///       could not be set up
+///   `InvalidUnicodeData` - A file has invalid characters in its name
+#[cfg(all(target_os = "linux", feature = "watch"))]
+pub fn watch_events_and_then(directory: impl AsRef<Path>, filenames: Vec<String>, process: impl FnMut(&PathBuf, WatchEvent) + Send + 'static) -> Result<WatchHandle, Error> {
+	FindOptions::new(directory).filenames(filenames).watch_events(process)
 }
 
 
-/// Find all files with the extensions provided in `extensions` and run them through the provided function `process`
+/// Find the most recently modified file of `directory`, like the latest backup
+/// 
+/// Directories are not candidates, but they are descended into when `recursive` is set. Files whose metadata
+/// or modification date cannot be read (like broken symlinks) are skipped, and among files modified at the
+/// same time the first one found is returned
 /// 
 /// # Variables
-///   `directory` - The directory from which to start the search
-///   `extensions` - A vector of all the extensions to be filtered
-///   `process` - Function that takes a PathBuf to a file `fn(&PathBuf)`
-///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
-///       If it is unset(`false`) a `file_processor::Error` will be returned
+///   `directory` - The directory from which to start the search (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `extensions` - The extensions files must have (see `find_by_extension_and_then`), or `None` for every file
+///   `recursive` - A boolean indicating if subdirectories should be searched too (depth-first)
+///       Symlinked directories are followed, but every directory is only walked once
 /// 
 /// # Return type
-///   `Result<(), file_processor::Error>`
+///   `Result<Option<PathBuf>, file_processor::Error>` - The newest file, or `None` when no file matches
 /// 
 /// # Errors
 ///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
 ///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
 ///   `NullDirectory` - The provided directory is null
-pub fn find_by_extension_and_then(directory: PathBuf, extensions: Vec<String>, process: fn(&PathBuf), ignore_fail: bool) -> Result<(), Error> {
-	match directory.to_str() {
-		Some(dir) => {
-			if !directory.exists() {
-				return Err(Error::DirectoryDoesNotExist(directory));
-			}
+///   `IoError(std::io::Error)` - A directory could not be read (permissions, removed during the search...)
+pub fn most_recent(directory: impl AsRef<Path>, extensions: Option<Vec<String>>, recursive: bool) -> Result<Option<PathBuf>, Error> {
+	let mut options = FindOptions::new(directory).entry_kind(EntryKind::FilesOnly).recursive(recursive);
+	if let Some(extensions) = extensions {
+		options = options.extensions(extensions);
+	}
 
-			for entry in std::fs::read_dir(dir).unwrap() {
-				match entry {
-					Ok(e) => {
-						match e.path().extension() {
-							Some(extension) => {
-								match extension.to_str() {
-									Some(ext) => {
-										if extensions.contains(&String::from(ext)) {
-											process(&e.path());
-										}
-									},
-									None => continue,
-								}
-							},
-							None => continue,
-						}
-					},
-					_ => if !ignore_fail { return Err(Error::CouldNotOpenEntry) },
-				}
-			}
+	let mut search = search::Search::new(options, false, false);
+	let mut newest: Option<(std::time::SystemTime, PathBuf)> = None;
 
-			Ok(())
-		},
-		None => Err(Error::NullDirectory),
+	while let Some(next) = search.next_entry(&mut |_, _| ()) {
+		let path = next?;
+
+		if let Some(date) = search.metadata().and_then(|metadata| metadata.modified().ok()) {
+			if newest.as_ref().map_or(true, |(newest, _)| date > *newest) {
+				newest = Some((date, path));
+			}
+		}
 	}
+
+	Ok(newest.map(|(_, path)| path))
 }
 
 
-/// Find all files provided in `filenames` and run them through the provided function `process`
+/// Scan `directory` and build a `FileModify` record for every file found
+/// 
+/// Directories are not recorded, but they are descended into when `recursive` is set.
+/// A followed symlinked file is recorded with the date of its target
 /// 
 /// # Variables
-///   `directory` - The directory from which to start the search
-///   `filenames` - A vector of all the filenames to be searched
-///   `process` - Function that takes a PathBuf to a file
-///   `ignore_fail` - A boolean indicating if incorrect or corrupt paths should be errored on
-///       If it is unset(`false`) a `file_processor::Error` will be returned
+///   `directory` - The directory from which to start the scan (anything that is `AsRef<Path>`, like `&str` or `PathBuf`)
+///   `recursive` - A boolean indicating if subdirectories should be scanned too (depth-first)
 /// 
 /// # Return type
-///   `Result<(), file_processor::Error>`
+///   `Result<Vec<FileModify>, file_processor::Error>`
 /// 
 /// # Errors
 ///   `DirectoryDoesNotExist(std::path::PathBuf)` - The provided parent directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The provided parent directory is not a directory (a file)
 ///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
 ///   `NullDirectory` - The provided directory is null
-///   `InvalidUnicodeData` - A file has invalid characters in its name
-///   `MissingFiles(Vec<usize>)` - Indicates which files from the requested ones (`filenames`) have not been found
-pub fn find_and_then(directory: PathBuf, filenames: Vec<String>, process: fn(&PathBuf), ignore_fail: bool) -> Result<(), Error> {
-	let mut count = 0;
+///   `IoError(std::io::Error)` - A directory could not be read, or the metadata or modification date
+///       of a file could not be read (`modified()` is not supported on every platform)
+pub fn collect_modify_times(directory: impl AsRef<Path>, recursive: bool) -> Result<Vec<FileModify>, Error> {
+	let mut records = Vec::new();
 
-	let mut indexes: Vec<usize> = (0..filenames.len()).collect();
-
-	match directory.to_str() {
-		Some(dir) => {
-			if !directory.exists() {
-				return Err(Error::DirectoryDoesNotExist(directory.clone()));
-			}
+	FindOptions::new(directory).entry_kind(EntryKind::FilesOnly).recursive(recursive).run(&mut |path| {
+		let metadata = std::fs::metadata(path).map_err(Error::IoError)?;
 
-			for entry in std::fs::read_dir(dir).unwrap() {
-				match entry {
-					Ok(e) => {
-						match e.file_name().to_str() {
-							Some(name) => {
-								for (i, s) in filenames.iter().enumerate() {
-									if s == &String::from(name) {
-										process(&e.path());
-										count += 1;
-										indexes.remove(i);
-										break;
-									}
-								}
-							},
-
-							None => if !ignore_fail { return Err(Error::InvalidUnicodeData) }
-						}
+		records.push(FileModify::new(path.clone(), metadata.modified().map_err(Error::IoError)?));
 
-					},
-					_ => if !ignore_fail { return Err(Error::CouldNotOpenEntry) },
-				}
-			}
+		Ok(ControlFlow::Continue(()))
+	})?;
 
-			if count == filenames.len() {
-				Ok(())
-			} else {
-				Err(Error::MissingFiles(indexes.clone()))
-			}
-		},
-		None => Err(Error::NullDirectory),
-	}
+	Ok(records)
 }
 
 
 /// A structure representing a filename and its last modification date
 /// 
-/// It is used to keep record of state changes
-#[derive(Debug)]
+/// It is used to keep record of state changes. Records are ordered by filename, then by date
+/// 
+/// `serde::Serialize` and `serde::Deserialize` are not derived.
+/// Records are persisted through the `SaveFileFormat`s instead (`save_records`), and an external
+/// serializer can be fed with `filename`, `hash` and `unix_timestamp`, then the record rebuilt with
+/// `from_unix_timestamp`, which round-trips the date across platforms
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct FileModify {
 	filename: std::path::PathBuf,
 	date: std::time::SystemTime,
+	hash: Option<String>,
 }
 
 impl FileModify {
@@ -211,12 +3264,353 @@ impl FileModify {
 		FileModify{
 			filename,
 			date,
+			hash: None,
+		}
+	}
+
+	/// Build a record of the file at `path` with its last modification date and the digest of its contents
+	/// 
+	/// Modification dates change without the contents changing (`touch`, `git checkout`, clock skew), so
+	/// `diff_snapshots` compares the hashes of records that both have one instead of their dates.
+	/// The hash is a lowercase hexadecimal string, as computed by `find_and_hash`
+	/// 
+	/// # Errors
+	///   `UnsupportedAlgorithm(HashAlgorithm)` - The cargo feature enabling `algorithm` is not compiled in
+	///   `IoError(std::io::Error)` - The metadata or the contents of the file could not be read
+	pub fn with_hash(path: impl AsRef<Path>, algorithm: HashAlgorithm) -> Result<FileModify, Error> {
+		let path = path.as_ref();
+		let date = std::fs::metadata(path).and_then(|metadata| metadata.modified()).map_err(Error::IoError)?;
+
+		Ok(FileModify{
+			filename: path.to_path_buf(),
+			date,
+			hash: Some(hash::digest_file(path, algorithm)?),
+		})
+	}
+
+	/// The path of the file this record tracks
+	pub fn filename(&self) -> &Path {
+		&self.filename
+	}
+
+	/// The last modification date of the file
+	pub fn date(&self) -> std::time::SystemTime {
+		self.date
+	}
+
+	/// The digest of the contents of the file, for records built with `FileModify::with_hash`
+	pub fn hash(&self) -> Option<&str> {
+		self.hash.as_deref()
+	}
+
+	/// Whether the file was modified after `reference`, like since the last time it was processed
+	pub fn is_newer_than(&self, reference: std::time::SystemTime) -> bool {
+		self.date > reference
+	}
+
+	/// Whether the file was modified before `reference`. A record dated exactly `reference` is neither
+	/// newer nor older
+	pub fn is_older_than(&self, reference: std::time::SystemTime) -> bool {
+		self.date < reference
+	}
+
+	/// Build a record from a date given as seconds and nanoseconds since the Unix epoch
+	/// 
+	/// # Errors
+	///   `InvalidSnapshot(String)` - `nanos` is not below one second or the date overflows `SystemTime`
+	pub fn from_unix_timestamp(filename: std::path::PathBuf, secs: u64, nanos: u32) -> Result<FileModify, Error> {
+		Ok(FileModify::new(filename, snapshot::from_unix(secs, nanos)?))
+	}
+
+	/// The date of this record as seconds and nanoseconds since the Unix epoch
+	/// 
+	/// This is the representation every `SaveFileFormat` stores, so it round-trips across platforms.
+	/// It can be used to (de)serialize records with an external serializer
+	/// 
+	/// # Errors
+	///   `InvalidSnapshot(String)` - The date is earlier than the Unix epoch
+	pub fn unix_timestamp(&self) -> Result<(u64, u32), Error> {
+		snapshot::to_unix(self.date)
+	}
+
+	/// Save this record to the file at `path` encoded in the given `format`
+	/// 
+	/// The date is stored as seconds and nanoseconds since the Unix epoch, so it round-trips across platforms.
+	/// The record is written to a temporary file next to `path` and renamed over it, so a crash while saving
+	/// never leaves a truncated file: `path` keeps either its old contents or the complete new ones
+	/// 
+	/// # Errors
+	///   `UnsupportedFormat(SaveFileFormat)` - The cargo feature enabling `format` is not compiled in
+	///   `InvalidUnicodeData` - The filename is not valid Unicode, which every format stores it as
+	///   `InvalidSnapshot(String)` - The date is earlier than the Unix epoch, or later than the year 9999 in TOML
+	///   `IoError(std::io::Error)` - The file could not be written
+	pub fn save(&self, path: impl AsRef<Path>, format: SaveFileFormat) -> Result<(), Error> {
+		let bytes = snapshot::encode(self, format)?;
+		snapshot::write_atomic(path.as_ref(), &bytes)
+	}
+
+	/// Save this record like `FileModify::save`, but write `path` directly instead of through a temporary file
+	/// 
+	/// It is faster and keeps the file identity (links, permissions), but a crash while saving can leave
+	/// a truncated file behind
+	/// 
+	/// # Errors
+	///   The same as `FileModify::save`
+	pub fn save_in_place(&self, path: impl AsRef<Path>, format: SaveFileFormat) -> Result<(), Error> {
+		let bytes = snapshot::encode(self, format)?;
+		std::fs::write(path, bytes).map_err(Error::IoError)
+	}
+
+	/// Load a record saved with `FileModify::save` from the file at `path`
+	/// 
+	/// # Errors
+	///   `UnsupportedFormat(SaveFileFormat)` - The cargo feature enabling `format` is not compiled in
+	///   `InvalidUnicodeData` - The format stores text and the file is not valid Unicode, or the filename is not
+	///   `InvalidSnapshot(String)` - The file contents are not a valid record
+	///   `IoError(std::io::Error)` - The file could not be read
+	pub fn load(path: impl AsRef<Path>, format: SaveFileFormat) -> Result<FileModify, Error> {
+		let bytes = std::fs::read(path).map_err(Error::IoError)?;
+		snapshot::decode(&bytes, format)
+	}
+
+	/// Save a whole snapshot of `records` to the file at `path` encoded in the given `format`
+	/// 
+	/// Deprecated in favor of `save_records`, which it calls
+	/// 
+	/// # Errors
+	///   The same as `save_records`
+	#[deprecated(note = "use save_records")]
+	pub fn save_all(records: &[FileModify], path: impl AsRef<Path>, format: SaveFileFormat) -> Result<(), Error> {
+		save_records(records, path, format)
+	}
+
+	/// Load a snapshot saved with `save_records` from the file at `path`, in the order it was saved
+	/// 
+	/// Deprecated in favor of `load_records`, which it calls
+	/// 
+	/// # Errors
+	///   The same as `load_records`
+	#[deprecated(note = "use load_records")]
+	pub fn load_all(path: impl AsRef<Path>, format: SaveFileFormat) -> Result<Vec<FileModify>, Error> {
+		load_records(path, format)
+	}
+}
+
+/// Encode `records` as a JSON manifest, an array of the objects `FileModify::save` writes with one record per line
+/// 
+/// Requires the `json` feature. Dates are stored as seconds and nanoseconds since the Unix epoch, so a manifest
+/// round-trips through `from_json_manifest` across platforms
+/// 
+/// # Errors
+///   `InvalidUnicodeData` - A filename is not valid Unicode
+///   `InvalidSnapshot(String)` - A date is earlier than the Unix epoch
+#[cfg(feature = "json")]
+pub fn to_json_manifest(records: &[FileModify]) -> Result<String, Error> {
+	snapshot::json::encode_all(records)
+}
+
+/// Decode the records of a JSON manifest written by `to_json_manifest`, in the order they are listed
+/// 
+/// Requires the `json` feature. Unknown fields of the records are ignored
+/// 
+/// # Errors
+///   `InvalidSnapshot(String)` - `manifest` is not an array of valid records
+#[cfg(feature = "json")]
+pub fn from_json_manifest(manifest: &str) -> Result<Vec<FileModify>, Error> {
+	snapshot::json::decode_all(manifest)
+}
+
+/// Write `records` to `writer` as JSON Lines, the object `FileModify::save` writes for every record on its own line
+/// 
+/// Requires the `json` feature. Unlike a manifest of `to_json_manifest`, records can be appended to a file later
+/// (opened with `append`) without rewriting it, and read back one line at a time by `read_jsonl_manifest`.
+/// Every line ends with `\n`, and `writer` is not flushed
+/// 
+/// # Errors
+///   `InvalidUnicodeData` - A filename is not valid Unicode
+///   `InvalidSnapshot(String)` - A date is earlier than the Unix epoch
+///   `IoError(std::io::Error)` - `writer` could not be written to
+#[cfg(feature = "json")]
+pub fn write_jsonl_manifest(records: &[FileModify], mut writer: impl std::io::Write) -> Result<(), Error> {
+	for record in records {
+		let mut line = snapshot::json::encode(record)?;
+		line.push('\n');
+
+		writer.write_all(line.as_bytes()).map_err(Error::IoError)?;
+	}
+
+	Ok(())
+}
+
+/// Read the records of JSON Lines written by `write_jsonl_manifest` from `reader`, in the order they are listed
+/// 
+/// Requires the `json` feature. The input is read one line at a time, so it is never held in memory as a whole.
+/// Empty lines are ignored, and unknown fields of the records too
+/// 
+/// # Errors
+///   `InvalidSnapshot(String)` - A line is not a valid record, its number is given in the message
+///   `IoError(std::io::Error)` - `reader` could not be read or is not valid UTF-8
+#[cfg(feature = "json")]
+pub fn read_jsonl_manifest(reader: impl BufRead) -> Result<Vec<FileModify>, Error> {
+	let mut records = Vec::new();
+
+	for (i, line) in reader.lines().enumerate() {
+		let line = line.map_err(Error::IoError)?;
+
+		if line.trim().is_empty() {
+			continue;
+		}
+
+		match snapshot::json::decode(&line) {
+			Ok(record) => records.push(record),
+			Err(Error::InvalidSnapshot(msg)) => return Err(Error::InvalidSnapshot(format!("{} (line {})", msg, i + 1))),
+			Err(err) => return Err(err),
+		}
+	}
+
+	Ok(records)
+}
+
+
+/// Save `records` to the file at `path` in a `format` chosen at runtime (from a command line flag...)
+/// 
+/// Every `SaveFileFormat` goes through this entry point. Like `FileModify::save`, the file is replaced atomically.
+/// JSON stores the manifest of `to_json_manifest`, and Bincode the layout of bincode 1 with its default options:
+/// the record count as a `u64`, then for every record the length of its filename as a `u64`, its UTF-8 bytes,
+/// its date as seconds (`u64`) and nanoseconds (`u32`) since the Unix epoch, and its hash as an `Option<String>`
+/// (a `0` byte, or a `1` byte then the hash like the filename). Bincode integers are little-endian whatever
+/// the machine, so snapshots load on any platform. TOML stores a `[[record]]` table per record, with the date
+/// as a UTC date-time (`date = 2019-01-01T00:00:00.5Z`) that is easy to edit by hand and to review in a diff.
+/// It is written with all its nanoseconds, but TOML only requires parsers to keep milliseconds, so other
+/// tools editing the file may lose the sub-second precision. Dates after the year 9999 cannot be stored.
+/// RON stores a sequence of the structs the `ron` crate writes for `FileModify`, one per line.
+/// The text formats only store the hash of the records that have one, in a `hash` field
+/// 
+/// # Errors
+///   `UnsupportedFormat(SaveFileFormat)` - The cargo feature enabling `format` is not compiled in, or the format
+///       has no implementation (`CBOR`, `YAML`, `MessagePack`)
+///   `InvalidUnicodeData` - A filename is not valid Unicode, which every format stores it as
+///   `InvalidSnapshot(String)` - A date is earlier than the Unix epoch, or later than the year 9999 in TOML
+///   `IoError(std::io::Error)` - The file could not be written
+pub fn save_records(records: &[FileModify], path: impl AsRef<Path>, format: SaveFileFormat) -> Result<(), Error> {
+	let bytes = snapshot::encode_all(records, format)?;
+	snapshot::write_atomic(path.as_ref(), &bytes)
+}
+
+/// Load the records saved with `save_records` in the given `format` from the file at `path`
+/// 
+/// # Errors
+///   `UnsupportedFormat(SaveFileFormat)` - The cargo feature enabling `format` is not compiled in, or the format
+///       has no implementation (`CBOR`, `YAML`, `MessagePack`)
+///   `InvalidUnicodeData` - The format stores text and the file is not valid Unicode, or a filename is not
+///   `InvalidSnapshot(String)` - The file contents are not a valid snapshot
+///   `IoError(std::io::Error)` - The file could not be read
+pub fn load_records(path: impl AsRef<Path>, format: SaveFileFormat) -> Result<Vec<FileModify>, Error> {
+	let bytes = std::fs::read(path).map_err(Error::IoError)?;
+	snapshot::decode_all(&bytes, format)
+}
+
+/// Save `records` like `save_records`, in the format named by the extension of `path` (`snapshot.toml`)
+/// 
+/// See `SaveFileFormat::from_extension` for the extensions recognized
+/// 
+/// # Errors
+///   `UnknownFormat(std::path::PathBuf)` - The extension of `path` names no format
+///   The others of `save_records`
+pub fn save_records_auto(records: &[FileModify], path: impl AsRef<Path>) -> Result<(), Error> {
+	let path = path.as_ref();
+	save_records(records, path, SaveFileFormat::of_path(path)?)
+}
+
+/// Load records like `load_records`, in the format named by the extension of `path` (`snapshot.toml`)
+/// 
+/// # Errors
+///   `UnknownFormat(std::path::PathBuf)` - The extension of `path` names no format
+///   The others of `load_records`
+pub fn load_records_auto(path: impl AsRef<Path>) -> Result<Vec<FileModify>, Error> {
+	let path = path.as_ref();
+	load_records(path, SaveFileFormat::of_path(path)?)
+}
+
+
+/// The changes between two snapshots of `FileModify` records
+#[derive(Debug, Default, Clone)]
+pub struct SnapshotDiff {
+	/// Files present in the new snapshot only
+	pub added: Vec<PathBuf>,
+	/// Files present in the old snapshot only
+	pub removed: Vec<PathBuf>,
+	/// Files present in both snapshots with a different date
+	pub modified: Vec<PathBuf>,
+}
+
+impl SnapshotDiff {
+	/// Whether the two snapshots are identical
+	pub fn is_empty(&self) -> bool {
+		self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+	}
+}
+
+
+/// Compare two snapshots of `FileModify` records (as built by `collect_modify_times`)
+/// 
+/// Records are matched by filename. When both records of a file have a content hash (`FileModify::with_hash`)
+/// it is modified if the hashes differ, whatever the dates. Otherwise it is modified if the dates differ,
+/// regardless of which date is the newest
+/// 
+/// # Variables
+///   `old` - The earlier snapshot
+///   `new` - The later snapshot
+/// 
+/// # Return type
+///   `SnapshotDiff` - Added and modified files follow the order of `new`, removed ones the order of `old`
+pub fn diff_snapshots(old: &[FileModify], new: &[FileModify]) -> SnapshotDiff {
+	let old_records: HashMap<&Path, &FileModify> = old.iter().map(|m| (m.filename(), m)).collect();
+	let new_records: HashMap<&Path, &FileModify> = new.iter().map(|m| (m.filename(), m)).collect();
+
+	let mut diff = SnapshotDiff::default();
+
+	for record in new {
+		match old_records.get(record.filename()) {
+			Some(previous) => {
+				let changed = match (previous.hash(), record.hash()) {
+					(Some(a), Some(b)) => a != b,
+					_ => previous.date() != record.date(),
+				};
+
+				if changed {
+					diff.modified.push(record.filename().to_path_buf());
+				}
+			},
+			None => diff.added.push(record.filename().to_path_buf()),
+		}
+	}
+
+	for record in old {
+		if !new_records.contains_key(record.filename()) {
+			diff.removed.push(record.filename().to_path_buf());
 		}
 	}
+
+	diff
+}
+
+
+/// The digests `find_and_hash` can compute
+/// 
+/// Each algorithm is enabled by the cargo feature of the same name in lowercase (`sha256`, `md5`)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HashAlgorithm {
+	SHA256,
+	MD5,
 }
 
+
 /// An enum listing all possibles file types to save `FileModify`'s into
-#[derive(Debug, Copy, Clone)]
+/// 
+/// Each format is enabled by the cargo feature of the same name in lowercase (`json`...). `CBOR`, `YAML` and
+/// `MessagePack` are not implemented yet and always return `Error::UnsupportedFormat`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum SaveFileFormat {
 	JSON,
 	Bincode,
@@ -227,20 +3621,74 @@ pub enum SaveFileFormat {
 	RON,
 }
 
+impl SaveFileFormat {
+	/// The format usually stored in files with extension `ext`, compared ignoring ASCII case
+	/// 
+	/// `json`, `bin` or `bincode`, `cbor`, `yaml` or `yml`, `toml`, `msgpack` or `mp`, `ron`
+	pub fn from_extension(ext: &str) -> Option<SaveFileFormat> {
+		let format = match ext.to_ascii_lowercase().as_str() {
+			"json" => SaveFileFormat::JSON,
+			"bin" | "bincode" => SaveFileFormat::Bincode,
+			"cbor" => SaveFileFormat::CBOR,
+			"yaml" | "yml" => SaveFileFormat::YAML,
+			"toml" => SaveFileFormat::TOML,
+			"msgpack" | "mp" => SaveFileFormat::MessagePack,
+			"ron" => SaveFileFormat::RON,
+			_ => return None,
+		};
+
+		Some(format)
+	}
+
+	/// The format of the file at `path`, from its extension (see `SaveFileFormat::from_extension`)
+	fn of_path(path: &Path) -> Result<SaveFileFormat, Error> {
+		match path.extension().and_then(|ext| ext.to_str()).and_then(SaveFileFormat::from_extension) {
+			Some(format) => Ok(format),
+			None => Err(Error::UnknownFormat(path.to_path_buf())),
+		}
+	}
+}
+
 
 /// Error types
 ///   `DirectoryDoesNotExist(std::path::PathBuf)` - The directory does not exist
+///   `NotADirectory(std::path::PathBuf)` - The directory to search is a file
 ///   `CouldNotOpenEntry` - There was an error while examining a directory entry (`std::fs::DirEntry`)
 ///   `NullDirectory` - The directory is null
 ///   `InvalidUnicodeData` - A file has invalid characters in its name
-///   `MissingFiles(Vec<usize>)` - Indicates files index which could not be found
-#[derive(Debug, Clone)]
+///   `MissingFiles(Vec<String>)` - Indicates the names of the files which could not be found
+///   `IoError(std::io::Error)` - An underlying IO operation failed
+///   `UnsupportedFormat(SaveFileFormat)` - The requested save format is not compiled in
+///   `InvalidSnapshot(String)` - Saved data could not be encoded or decoded
+///   `InvalidPattern(String)` - A filename pattern could not be compiled
+///   `UnexpectedSymlink(std::path::PathBuf)` - A symbolic link was found while walking with `SymlinkPolicy::Error`
+///   `UnsupportedAlgorithm(HashAlgorithm)` - The requested hash algorithm is not compiled in
+///   `Cancelled` - The search was stopped through its `CancellationToken`
+///   `TimedOut` - The search ran for longer than its `FindOptions::timeout`
+///   `FileDoesNotExist(std::path::PathBuf)` - A checked entry does not exist
+///   `UnexpectedKind(std::path::PathBuf, FileKind)` - A checked entry is not of the expected kind
+///   `UnknownFormat(std::path::PathBuf)` - No save format is named by the extension of a file
+///   `Ambiguous(Vec<std::path::PathBuf>)` - Several files match where exactly one was expected
+#[derive(Debug)]
 pub enum Error {
 	InvalidUnicodeData,
 	NullDirectory,
 	CouldNotOpenEntry,
 	DirectoryDoesNotExist(PathBuf),
-	MissingFiles(Vec<usize>),
+	NotADirectory(PathBuf),
+	MissingFiles(Vec<String>),
+	IoError(std::io::Error),
+	UnsupportedFormat(SaveFileFormat),
+	InvalidSnapshot(String),
+	InvalidPattern(String),
+	UnexpectedSymlink(PathBuf),
+	UnsupportedAlgorithm(HashAlgorithm),
+	Cancelled,
+	TimedOut,
+	FileDoesNotExist(PathBuf),
+	UnexpectedKind(PathBuf, FileKind),
+	UnknownFormat(PathBuf),
+	Ambiguous(Vec<PathBuf>),
 }
 
 impl std::fmt::Display for Error {
@@ -250,7 +3698,185 @@ impl std::fmt::Display for Error {
 			Error::NullDirectory => write!(f, "Null directory does not exist"),
 			Error::CouldNotOpenEntry => write!(f, "Could not open entry"),
 			Error::DirectoryDoesNotExist(dir) => write!(f, "Directory does not exist:\n{:?}", dir),
-			Error::MissingFiles(indexes) => write!(f, "Could not find all files\nMissing files: {:?}", indexes),
+			Error::NotADirectory(path) => write!(f, "Not a directory:\n{:?}", path),
+			Error::MissingFiles(names) => write!(f, "Could not find all files\nMissing files: {:?}", names),
+			Error::IoError(err) => write!(f, "IO error: {}", err),
+			Error::UnsupportedFormat(format) => write!(f, "Unsupported save format: {:?}", format),
+			Error::InvalidSnapshot(msg) => write!(f, "Invalid snapshot: {}", msg),
+			Error::InvalidPattern(msg) => write!(f, "Invalid pattern: {}", msg),
+			Error::UnexpectedSymlink(path) => write!(f, "Unexpected symbolic link:\n{:?}", path),
+			Error::UnsupportedAlgorithm(algorithm) => write!(f, "Unsupported hash algorithm: {:?}", algorithm),
+			Error::Cancelled => write!(f, "The search was cancelled"),
+			Error::TimedOut => write!(f, "The search timed out"),
+			Error::FileDoesNotExist(path) => write!(f, "File does not exist:\n{:?}", path),
+			Error::UnexpectedKind(path, kind) => write!(f, "Expected a {:?}:\n{:?}", kind, path),
+			Error::UnknownFormat(path) => write!(f, "No save format has the extension of:\n{:?}", path),
+			Error::Ambiguous(paths) => write!(f, "Expected a single file, found:\n{:?}", paths),
+		}
+	}
+}
+
+/// Errors are equal when they are the same variant with equal contents, so results can be compared in tests
+/// (`assert_eq!(result, Err(Error::NullDirectory))`). `std::io::Error` cannot be compared, so `IoError`s are equal
+/// when their `std::io::ErrorKind` is
+impl PartialEq for Error {
+	fn eq(&self, other: &Error) -> bool {
+		match (self, other) {
+			(Error::IoError(a), Error::IoError(b)) => a.kind() == b.kind(),
+			(Error::DirectoryDoesNotExist(a), Error::DirectoryDoesNotExist(b)) => a == b,
+			(Error::NotADirectory(a), Error::NotADirectory(b)) => a == b,
+			(Error::MissingFiles(a), Error::MissingFiles(b)) => a == b,
+			(Error::UnsupportedFormat(a), Error::UnsupportedFormat(b)) => a == b,
+			(Error::InvalidSnapshot(a), Error::InvalidSnapshot(b)) => a == b,
+			(Error::InvalidPattern(a), Error::InvalidPattern(b)) => a == b,
+			(Error::UnexpectedSymlink(a), Error::UnexpectedSymlink(b)) => a == b,
+			(Error::UnsupportedAlgorithm(a), Error::UnsupportedAlgorithm(b)) => a == b,
+			(Error::FileDoesNotExist(a), Error::FileDoesNotExist(b)) => a == b,
+			(Error::UnexpectedKind(a, kind_a), Error::UnexpectedKind(b, kind_b)) => a == b && kind_a == kind_b,
+			(Error::UnknownFormat(a), Error::UnknownFormat(b)) => a == b,
+			(Error::Ambiguous(a), Error::Ambiguous(b)) => a == b,
+			(Error::InvalidUnicodeData, Error::InvalidUnicodeData)
+			| (Error::NullDirectory, Error::NullDirectory)
+			| (Error::CouldNotOpenEntry, Error::CouldNotOpenEntry)
+			| (Error::Cancelled, Error::Cancelled)
+			| (Error::TimedOut, Error::TimedOut) => true,
+			_ => false,
+		}
+	}
+}
+
+/// `std::io::Error` cannot be cloned, so a cloned `IoError` is rebuilt from the OS error code when there is one,
+/// and otherwise from the `std::io::ErrorKind` and message of the original
+impl Clone for Error {
+	fn clone(&self) -> Error {
+		match self {
+			Error::IoError(err) => Error::IoError(match err.raw_os_error() {
+				Some(code) => std::io::Error::from_raw_os_error(code),
+				None => std::io::Error::new(err.kind(), err.to_string()),
+			}),
+			Error::InvalidUnicodeData => Error::InvalidUnicodeData,
+			Error::NullDirectory => Error::NullDirectory,
+			Error::CouldNotOpenEntry => Error::CouldNotOpenEntry,
+			Error::DirectoryDoesNotExist(path) => Error::DirectoryDoesNotExist(path.clone()),
+			Error::NotADirectory(path) => Error::NotADirectory(path.clone()),
+			Error::MissingFiles(names) => Error::MissingFiles(names.clone()),
+			Error::UnsupportedFormat(format) => Error::UnsupportedFormat(*format),
+			Error::InvalidSnapshot(msg) => Error::InvalidSnapshot(msg.clone()),
+			Error::InvalidPattern(msg) => Error::InvalidPattern(msg.clone()),
+			Error::UnexpectedSymlink(path) => Error::UnexpectedSymlink(path.clone()),
+			Error::UnsupportedAlgorithm(algorithm) => Error::UnsupportedAlgorithm(*algorithm),
+			Error::Cancelled => Error::Cancelled,
+			Error::TimedOut => Error::TimedOut,
+			Error::FileDoesNotExist(path) => Error::FileDoesNotExist(path.clone()),
+			Error::UnexpectedKind(path, kind) => Error::UnexpectedKind(path.clone(), *kind),
+			Error::UnknownFormat(path) => Error::UnknownFormat(path.clone()),
+			Error::Ambiguous(paths) => Error::Ambiguous(paths.clone()),
+		}
+	}
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::IoError(err) => Some(err),
+			_ => None,
 		}
 	}
-}
\ No newline at end of file
+}
+
+/// Lets `?` turn an `std::io::Error` into `Error::IoError`, in processing closures and helpers
+/// returning `Result<T, file_processor::Error>`
+impl From<std::io::Error> for Error {
+	fn from(err: std::io::Error) -> Error {
+		Error::IoError(err)
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::{diff_snapshots, process_parallel, Error, FileModify, IntoControlFlow, ProcessReport};
+
+	use std::ops::ControlFlow;
+	use std::path::PathBuf;
+	use std::sync::Mutex;
+	use std::time::{Duration, SystemTime};
+
+	fn record(name: &str, secs: u64) -> FileModify {
+		FileModify::new(PathBuf::from(name), SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+	}
+
+	fn hashed(name: &str, secs: u64, hash: &str) -> FileModify {
+		FileModify{ hash: Some(hash.to_string()), ..record(name, secs) }
+	}
+
+	#[test]
+	fn snapshots_are_diffed_by_name_and_date() {
+		let old = [record("kept", 1), record("removed", 1), record("touched", 1), record("older", 5)];
+		let new = [record("added", 1), record("older", 2), record("touched", 2), record("kept", 1)];
+
+		let diff = diff_snapshots(&old, &new);
+		assert_eq!(diff.added, [PathBuf::from("added")]);
+		assert_eq!(diff.removed, [PathBuf::from("removed")]);
+		// Whichever date is the newest, in the order of the new snapshot
+		assert_eq!(diff.modified, [PathBuf::from("older"), PathBuf::from("touched")]);
+
+		assert!(diff_snapshots(&old, &old).is_empty());
+	}
+
+	#[test]
+	fn hashes_take_precedence_over_dates() {
+		let old = [hashed("same", 1, "aa"), hashed("changed", 1, "aa"), record("unhashed", 1)];
+		let new = [hashed("same", 2, "aa"), hashed("changed", 1, "bb"), hashed("unhashed", 2, "aa")];
+
+		let diff = diff_snapshots(&old, &new);
+		assert!(diff.added.is_empty() && diff.removed.is_empty());
+		// Without a hash on both sides, the dates are compared
+		assert_eq!(diff.modified, [PathBuf::from("changed"), PathBuf::from("unhashed")]);
+	}
+
+	#[test]
+	fn process_results_convert_to_control_flow() {
+		assert_eq!(().into_control_flow(), ControlFlow::Continue(()));
+		assert_eq!(true.into_control_flow(), ControlFlow::Continue(()));
+		assert_eq!(false.into_control_flow(), ControlFlow::Break(()));
+		assert_eq!(ControlFlow::Break(()).into_control_flow(), ControlFlow::Break(()));
+	}
+
+	#[test]
+	fn every_path_is_processed_once_in_parallel() {
+		let paths: Vec<PathBuf> = (0..100).map(|i| PathBuf::from(i.to_string())).collect();
+
+		for workers in [1, 4, 200] {
+			let seen = Mutex::new(Vec::new());
+			process_parallel(&paths, workers, &|index, path| {
+				assert_eq!(*path, paths[index]);
+				seen.lock().unwrap().push(index);
+			});
+
+			let mut seen = seen.into_inner().unwrap();
+			seen.sort_unstable();
+			assert_eq!(seen, (0..100).collect::<Vec<_>>());
+		}
+
+		process_parallel(&[], 4, &|_, _| panic!("processed without paths"));
+	}
+
+	#[test]
+	fn reports_turn_into_their_first_failure() {
+		let report = |errors: Vec<(PathBuf, Error)>, missing: Vec<String>| ProcessReport{ processed: vec![1, 2], skipped: 3, errors, missing };
+
+		let success = report(Vec::new(), Vec::new());
+		assert!(success.is_success());
+		assert_eq!(success.into_result().unwrap(), [1, 2]);
+
+		let missing = report(Vec::new(), vec![String::from("a.txt")]);
+		assert!(!missing.is_success());
+		assert_eq!(missing.into_result().unwrap_err(), Error::MissingFiles(vec![String::from("a.txt")]));
+
+		// Failures come before the missing files
+		let failed = report(vec![(PathBuf::from("b"), Error::Cancelled), (PathBuf::from("c"), Error::TimedOut)], vec![String::from("a.txt")]);
+		assert!(!failed.is_success());
+		assert_eq!(failed.into_result().unwrap_err(), Error::Cancelled);
+	}
+}
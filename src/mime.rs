@@ -0,0 +1,259 @@
+//! Guessing the MIME type of files from their first bytes, or from their extension
+
+use crate::Error;
+
+use std::io::Read;
+use std::path::Path;
+
+
+
+/// How much of a file is read to recognize its format. Every signature (and the `ustar` marker
+/// of tar archives at offset 257) fits in it
+const SNIFF_SIZE: usize = 512;
+
+/// The type of files that are neither recognized nor text
+const UNKNOWN: &str = "application/octet-stream";
+
+
+/// Guess the MIME type of the file at `path`
+///
+/// Its contents are recognized first, then its extension. Unrecognized files are `text/plain` when they look
+/// like UTF-8 text and `application/octet-stream` otherwise
+pub(crate) fn detect_file(path: &Path) -> Result<String, Error> {
+	let mut file = std::fs::File::open(path).map_err(Error::IoError)?;
+	let mut head = Vec::with_capacity(SNIFF_SIZE);
+
+	file.by_ref().take(SNIFF_SIZE as u64).read_to_end(&mut head).map_err(Error::IoError)?;
+
+	let mime = sniff(&head)
+		.or_else(|| path.extension().and_then(|ext| ext.to_str()).and_then(by_extension))
+		.unwrap_or(if is_text(&head) { "text/plain" } else { UNKNOWN });
+
+	Ok(mime.to_string())
+}
+
+
+/// The MIME type of the format whose signature starts `head`, if any
+fn sniff(head: &[u8]) -> Option<&'static str> {
+	let at = |offset: usize, signature: &[u8]| head.get(offset..offset + signature.len()) == Some(signature);
+
+	let mime = match head {
+		[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => "image/png",
+		[0xFF, 0xD8, 0xFF, ..] => "image/jpeg",
+		[b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => "image/gif",
+		[b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "image/webp",
+		[b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => "audio/wav",
+		[b'R', b'I', b'F', b'F', _, _, _, _, b'A', b'V', b'I', b' ', ..] => "video/x-msvideo",
+		[b'I', b'I', 0x2A, 0x00, ..] | [b'M', b'M', 0x00, 0x2A, ..] => "image/tiff",
+		[0x00, 0x00, 0x01, 0x00, ..] => "image/vnd.microsoft.icon",
+		[b'8', b'B', b'P', b'S', ..] => "image/vnd.adobe.photoshop",
+		[b'B', b'M', ..] => "image/bmp",
+		[b'%', b'P', b'D', b'F', b'-', ..] => "application/pdf",
+		[b'P', b'K', 0x03, 0x04, ..] | [b'P', b'K', 0x05, 0x06, ..] | [b'P', b'K', 0x07, 0x08, ..] => "application/zip",
+		[0x1F, 0x8B, ..] => "application/gzip",
+		[b'B', b'Z', b'h', ..] => "application/x-bzip2",
+		[0xFD, b'7', b'z', b'X', b'Z', 0x00, ..] => "application/x-xz",
+		[b'7', b'z', 0xBC, 0xAF, 0x27, 0x1C, ..] => "application/x-7z-compressed",
+		[0x28, 0xB5, 0x2F, 0xFD, ..] => "application/zstd",
+		[b'R', b'a', b'r', b'!', 0x1A, 0x07, ..] => "application/vnd.rar",
+		[0x7F, b'E', b'L', b'F', ..] => "application/x-executable",
+		[b'M', b'Z', ..] => "application/vnd.microsoft.portable-executable",
+		[0x00, b'a', b's', b'm', ..] => "application/wasm",
+		[b'S', b'Q', b'L', b'i', b't', b'e', b' ', b'f', b'o', b'r', b'm', b'a', b't', b' ', b'3', 0x00, ..] => "application/vnd.sqlite3",
+		[b'I', b'D', b'3', ..] | [0xFF, 0xFB | 0xF3 | 0xF2, ..] => "audio/mpeg",
+		[b'f', b'L', b'a', b'C', ..] => "audio/flac",
+		[b'O', b'g', b'g', b'S', ..] => "audio/ogg",
+		[b'w', b'O', b'F', b'F', ..] => "font/woff",
+		[b'w', b'O', b'F', b'2', ..] => "font/woff2",
+		[0x00, 0x01, 0x00, 0x00, 0x00, ..] => "font/ttf",
+		[b'O', b'T', b'T', b'O', ..] => "font/otf",
+		// Matroska, of which WebM is a subset naming itself in its header
+		[0x1A, 0x45, 0xDF, 0xA3, ..] => if head.windows(4).any(|w| w == b"webm") { "video/webm" } else { "video/x-matroska" },
+		// ISO base media files (MP4 and its relatives) are told apart by the brand following `ftyp`
+		_ if at(4, b"ftyp") => match head.get(8..12) {
+			Some(b"qt  ") => "video/quicktime",
+			Some(b"M4A ") => "audio/mp4",
+			Some(b"avif") => "image/avif",
+			Some(b"heic" | b"heix") => "image/heic",
+			_ => "video/mp4",
+		},
+		_ if at(257, b"ustar") => "application/x-tar",
+		_ => return sniff_markup(head),
+	};
+
+	Some(mime)
+}
+
+/// The MIME type of text documents recognized by their opening markup, after any BOM and whitespace
+fn sniff_markup(head: &[u8]) -> Option<&'static str> {
+	let text = head.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(head);
+	let start = text.iter().position(|b| !b.is_ascii_whitespace())?;
+	let text = &text[start..];
+
+	let starts_with = |prefix: &[u8]| text.len() >= prefix.len() && text[..prefix.len()].eq_ignore_ascii_case(prefix);
+
+	if starts_with(b"<!doctype html") || starts_with(b"<html") {
+		Some("text/html")
+	} else if starts_with(b"<svg") || (starts_with(b"<?xml") && text.windows(4).any(|w| w == b"<svg")) {
+		Some("image/svg+xml")
+	} else if starts_with(b"<?xml") {
+		Some("text/xml")
+	} else {
+		None
+	}
+}
+
+
+/// The MIME type usually given to files with extension `ext`, compared ignoring ASCII case
+fn by_extension(ext: &str) -> Option<&'static str> {
+	let mime = match ext.to_ascii_lowercase().as_str() {
+		"txt" | "text" | "log" => "text/plain",
+		"html" | "htm" => "text/html",
+		"css" => "text/css",
+		"js" | "mjs" => "text/javascript",
+		"csv" => "text/csv",
+		"md" | "markdown" => "text/markdown",
+		"xml" => "text/xml",
+		"json" => "application/json",
+		"toml" => "application/toml",
+		"yaml" | "yml" => "application/yaml",
+		"svg" => "image/svg+xml",
+		"png" => "image/png",
+		"jpg" | "jpeg" => "image/jpeg",
+		"gif" => "image/gif",
+		"webp" => "image/webp",
+		"bmp" => "image/bmp",
+		"ico" => "image/vnd.microsoft.icon",
+		"tif" | "tiff" => "image/tiff",
+		"avif" => "image/avif",
+		"heic" => "image/heic",
+		"mp3" => "audio/mpeg",
+		"wav" => "audio/wav",
+		"flac" => "audio/flac",
+		"ogg" | "oga" => "audio/ogg",
+		"m4a" => "audio/mp4",
+		"mp4" | "m4v" => "video/mp4",
+		"mov" => "video/quicktime",
+		"webm" => "video/webm",
+		"mkv" => "video/x-matroska",
+		"avi" => "video/x-msvideo",
+		"pdf" => "application/pdf",
+		"zip" => "application/zip",
+		"gz" => "application/gzip",
+		"tar" => "application/x-tar",
+		"bz2" => "application/x-bzip2",
+		"xz" => "application/x-xz",
+		"7z" => "application/x-7z-compressed",
+		"zst" => "application/zstd",
+		"rar" => "application/vnd.rar",
+		"wasm" => "application/wasm",
+		"woff" => "font/woff",
+		"woff2" => "font/woff2",
+		"ttf" => "font/ttf",
+		"otf" => "font/otf",
+		_ => return None,
+	};
+
+	Some(mime)
+}
+
+
+/// Whether `head` looks like the start of a UTF-8 text: not empty, without NUL bytes nor invalid sequences
+///
+/// A sequence cut at the end of `head` is fine, since it may be completed by the rest of the file
+fn is_text(head: &[u8]) -> bool {
+	if head.is_empty() || head.contains(&0) {
+		return false;
+	}
+
+	match std::str::from_utf8(head) {
+		Ok(_) => true,
+		Err(err) => err.error_len().is_none(),
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::{detect_file, sniff};
+
+	fn padded(head: &[u8]) -> Vec<u8> {
+		let mut bytes = head.to_vec();
+		bytes.resize(64, 0);
+		bytes
+	}
+
+	#[test]
+	fn images_are_recognized_by_their_signature() {
+		assert_eq!(sniff(&padded(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A])), Some("image/png"));
+		// Only the full PNG signature
+		assert_eq!(sniff(&padded(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A])), None);
+		assert_eq!(sniff(&padded(b"RIFF\0\0\0\0WEBPVP8 ")), Some("image/webp"));
+		assert_eq!(sniff(&padded(b"RIFF\0\0\0\0WAVEfmt ")), Some("audio/wav"));
+	}
+
+	#[test]
+	fn archives_are_recognized_by_their_signature() {
+		assert_eq!(sniff(&padded(b"PK\x03\x04")), Some("application/zip"));
+		// An empty archive only has its end of central directory
+		assert_eq!(sniff(&padded(b"PK\x05\x06")), Some("application/zip"));
+		assert_eq!(sniff(&padded(b"PK\x01\x02")), None);
+		assert_eq!(sniff(&padded(&[0x1F, 0x8B, 0x08])), Some("application/gzip"));
+	}
+
+	#[test]
+	fn iso_media_files_are_told_apart_by_their_brand() {
+		let brand = |brand: &[u8]| {
+			let mut head = b"\0\0\0\x20ftyp".to_vec();
+			head.extend_from_slice(brand);
+			sniff(&padded(&head))
+		};
+
+		assert_eq!(brand(b"isom"), Some("video/mp4"));
+		assert_eq!(brand(b"qt  "), Some("video/quicktime"));
+		assert_eq!(brand(b"M4A "), Some("audio/mp4"));
+		assert_eq!(brand(b"avif"), Some("image/avif"));
+		assert_eq!(brand(b"heix"), Some("image/heic"));
+	}
+
+	#[test]
+	fn tar_archives_are_recognized_at_offset_257() {
+		let mut head = vec![b'a'; 512];
+		head[257..262].copy_from_slice(b"ustar");
+		assert_eq!(sniff(&head), Some("application/x-tar"));
+
+		// Anywhere else it is not a marker
+		let mut head = vec![b'a'; 512];
+		head[256..261].copy_from_slice(b"ustar");
+		assert_eq!(sniff(&head), None);
+		// Nor in a file too short to hold it
+		assert_eq!(sniff(&[b'a'; 260]), None);
+	}
+
+	#[test]
+	fn unrecognized_files_fall_back_on_their_extension_then_their_contents() {
+		let directory = std::env::temp_dir().join(format!("file_processor_mime_{}", std::process::id()));
+		std::fs::create_dir_all(&directory).unwrap();
+
+		let detect = |name: &str, contents: &[u8]| {
+			std::fs::write(directory.join(name), contents).unwrap();
+			detect_file(&directory.join(name)).unwrap()
+		};
+
+		let text = detect("notes", "plain text, é".as_bytes());
+		let binary = detect("blob", &[0x01, 0x00, 0xFE]);
+		let invalid = detect("latin1", &[b'c', b'a', b'f', 0xE9, b'!']);
+		let empty = detect("empty", b"");
+		let by_extension = detect("data.json", b"{}");
+		// The contents win over the extension
+		let renamed = detect("image.txt", &padded(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]));
+		std::fs::remove_dir_all(&directory).unwrap();
+
+		assert_eq!(text, "text/plain");
+		assert_eq!(binary, "application/octet-stream");
+		assert_eq!(invalid, "application/octet-stream");
+		assert_eq!(empty, "application/octet-stream");
+		assert_eq!(by_extension, "application/json");
+		assert_eq!(renamed, "image/png");
+	}
+}
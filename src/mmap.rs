@@ -0,0 +1,97 @@
+//! Read-only memory maps of files, through the `mmap` system call of Unix platforms
+
+use crate::Error;
+
+use std::convert::TryFrom;
+use std::os::raw::{c_int, c_void};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+
+
+const PROT_READ: c_int = 1;
+const MAP_PRIVATE: c_int = 2;
+
+extern "C" {
+	fn mmap(addr: *mut c_void, len: usize, prot: c_int, flags: c_int, fd: c_int, offset: isize) -> *mut c_void;
+	fn munmap(addr: *mut c_void, len: usize) -> c_int;
+}
+
+
+/// A read-only view of the contents of a file, mapped in memory instead of copied (see `find_and_mmap`)
+///
+/// It dereferences to the bytes of the file, and the mapping is removed when it is dropped. The pages are
+/// loaded by the operating system as they are read
+///
+/// A view is only made by `find_and_mmap`, which is `unsafe`: the bytes are borrowed from the file, so its caller
+/// promises that nothing modifies or truncates the file while the view lives
+#[derive(Debug)]
+pub struct Mmap {
+	ptr: *mut c_void,
+	len: usize,
+}
+
+// The mapping is read-only and owned by the view alone, and `open` requires the file not to change
+unsafe impl Send for Mmap {}
+unsafe impl Sync for Mmap {}
+
+impl Mmap {
+	/// Map the whole file at `path`
+	///
+	/// # Safety
+	///   The file must not be modified or truncated, by this program or another one, until the view is dropped
+	pub(crate) unsafe fn open(path: &Path) -> Result<Mmap, Error> {
+		let file = std::fs::File::open(path).map_err(Error::IoError)?;
+		let len = file.metadata().map_err(Error::IoError)?.len();
+
+		let len = match usize::try_from(len) {
+			Ok(len) => len,
+			Err(_) => return Err(Error::IoError(std::io::Error::new(std::io::ErrorKind::InvalidInput, "file too large to be mapped"))),
+		};
+
+		// Empty mappings are rejected by `mmap`, there is nothing to map anyway
+		if len == 0 {
+			return Ok(Mmap{ ptr: std::ptr::null_mut(), len });
+		}
+
+		// SAFETY: a new private read-only mapping of an open file, checked for failure below.
+		// The file can be closed once it is mapped
+		let ptr = unsafe { mmap(std::ptr::null_mut(), len, PROT_READ, MAP_PRIVATE, file.as_raw_fd(), 0) };
+
+		// `MAP_FAILED`
+		if ptr as isize == -1 {
+			return Err(Error::IoError(std::io::Error::last_os_error()));
+		}
+
+		Ok(Mmap{ ptr, len })
+	}
+}
+
+impl std::ops::Deref for Mmap {
+	type Target = [u8];
+
+	fn deref(&self) -> &[u8] {
+		if self.len == 0 {
+			return &[];
+		}
+
+		// SAFETY: `ptr` is a live mapping of `len` readable bytes until `self` is dropped, which do not change
+		// as `open` requires
+		unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+	}
+}
+
+impl AsRef<[u8]> for Mmap {
+	fn as_ref(&self) -> &[u8] {
+		self
+	}
+}
+
+impl Drop for Mmap {
+	fn drop(&mut self) {
+		if self.len != 0 {
+			// SAFETY: `ptr` and `len` describe a mapping made by `open`, which nothing borrows anymore
+			unsafe { munmap(self.ptr, self.len) };
+		}
+	}
+}
@@ -0,0 +1,655 @@
+//! Regular expressions for matching file names, in a time linear in the length of the name
+//!
+//! Supports the commonly used syntax:
+//!   `.` - Any character except a newline
+//!   `^`, `$` - Start and end of the text
+//!   `[abc]`, `[a-z]`, `[^abc]` - Character sets, which may contain the classes below
+//!   `\d`, `\w`, `\s` (and their negations `\D`, `\W`, `\S`) - ASCII digits, word characters
+//!       (alphanumerics and `_`) and whitespace
+//!   `(...)`, `(?:...)` - Groups (there are no captures)
+//!   `a|b` - Alternation
+//!   `*`, `+`, `?`, `{n}`, `{n,}`, `{n,m}` - Repetition. The lazy forms (`*?`...) are accepted, and match
+//!       the same names since there are no captures
+//!
+//! Any other escaped character (`\.`, `\\`...) matches itself.
+//! A pattern matches when it matches any part of the text; use `^` and `$` to match all of it
+
+use crate::Error;
+
+
+
+/// The most instructions a compiled expression can have, as counted repetitions are copied
+/// (`(a{100}){100}` is 10000 of them)
+const MAX_PROGRAM_SIZE: usize = 100_000;
+
+/// The deepest groups and repetitions can be nested. It keeps malformed patterns from overflowing the
+/// stack of the recursive parser and compiler
+const MAX_DEPTH: usize = 64;
+
+
+/// A compiled regular expression
+#[derive(Debug, Clone)]
+pub(crate) struct Regex {
+	program: Vec<Inst>,
+}
+
+/// The syntax tree of an expression
+#[derive(Debug, Clone)]
+enum Node {
+	Empty,
+	Char(char),
+	Any,
+	Set(Set),
+	Start,
+	End,
+	Concat(Vec<Node>),
+	Alternation(Vec<Node>),
+	Repeat {
+		node: Box<Node>,
+		min: usize,
+		max: Option<usize>,
+	},
+}
+
+/// An instruction of the automaton an expression compiles to, the ones after `Split` and `Jump`
+/// consume no character
+#[derive(Debug, Clone)]
+enum Inst {
+	Char(char),
+	Any,
+	Set(Set),
+	Start,
+	End,
+	/// Go on at both targets
+	Split(usize, usize),
+	Jump(usize),
+	Match,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Perl {
+	Digit,
+	Word,
+	Space,
+}
+
+impl Perl {
+	fn matches(self, c: char) -> bool {
+		match self {
+			Perl::Digit => c.is_ascii_digit(),
+			Perl::Word => c.is_ascii_alphanumeric() || c == '_',
+			Perl::Space => c.is_ascii_whitespace(),
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+struct Set {
+	negated: bool,
+	ranges: Vec<(char, char)>,
+	classes: Vec<(Perl, bool)>,
+}
+
+impl Set {
+	fn matches(&self, c: char) -> bool {
+		let found = self.ranges.iter().any(|(lo, hi)| *lo <= c && c <= *hi)
+			|| self.classes.iter().any(|(class, negated)| class.matches(c) != *negated);
+
+		found != self.negated
+	}
+}
+
+
+impl Regex {
+	/// Compile `pattern`
+	///
+	/// # Errors
+	///   `InvalidPattern(String)` - The pattern is not a valid regular expression, it nests groups or
+	///       repetitions more than 64 levels deep, or it is too large once its counted repetitions are expanded
+	pub(crate) fn new(pattern: &str) -> Result<Regex, Error> {
+		let mut parser = Parser{
+			pattern,
+			chars: pattern.chars().collect(),
+			pos: 0,
+			depth: 0,
+		};
+
+		let root = parser.parse_alternation()?;
+
+		if parser.peek().is_some() {
+			return Err(parser.error("unmatched `)`"));
+		}
+
+		if size(&root) > MAX_PROGRAM_SIZE {
+			return Err(Error::InvalidPattern(format!("the pattern is too large once compiled (more than {} instructions)", MAX_PROGRAM_SIZE)));
+		}
+
+		let mut program = Vec::new();
+		compile(&root, &mut program);
+		program.push(Inst::Match);
+
+		Ok(Regex{ program })
+	}
+
+	/// Whether the expression matches any part of `text`
+	///
+	/// The automaton is run on every position at once (a Pike VM without captures), so it takes a time
+	/// proportional to the length of `text` times the size of the expression, whatever the expression
+	pub(crate) fn is_match(&self, text: &str) -> bool {
+		let text: Vec<char> = text.chars().collect();
+		let mut current = Threads::new(self.program.len());
+		let mut next = Threads::new(self.program.len());
+
+		for pos in 0..=text.len() {
+			// A match starting at `pos`, so any part of the text can match
+			if self.follow(0, pos, &text, &mut current) {
+				return true;
+			}
+
+			let c = match text.get(pos) {
+				Some(c) => *c,
+				None => break,
+			};
+
+			next.clear();
+			for i in 0..current.len {
+				let pc = current.dense[i];
+				let consumed = match &self.program[pc] {
+					Inst::Char(expected) => c == *expected,
+					Inst::Any => c != '\n',
+					Inst::Set(set) => set.matches(c),
+					_ => false,
+				};
+
+				if consumed && self.follow(pc + 1, pos + 1, &text, &mut next) {
+					return true;
+				}
+			}
+
+			std::mem::swap(&mut current, &mut next);
+		}
+
+		false
+	}
+
+	/// Add the thread at `pc` to `threads`, following the instructions that consume no character.
+	/// Returns `true` when one of them reaches `Match`
+	fn follow(&self, pc: usize, pos: usize, text: &[char], threads: &mut Threads) -> bool {
+		let mut stack = vec![pc];
+
+		while let Some(pc) = stack.pop() {
+			if !threads.insert(pc) {
+				continue;
+			}
+
+			match self.program[pc] {
+				Inst::Match => return true,
+				Inst::Jump(target) => stack.push(target),
+				Inst::Split(first, second) => {
+					stack.push(second);
+					stack.push(first);
+				},
+				Inst::Start => if pos == 0 { stack.push(pc + 1) },
+				Inst::End => if pos == text.len() { stack.push(pc + 1) },
+				Inst::Char(_) | Inst::Any | Inst::Set(_) => {},
+			}
+		}
+
+		false
+	}
+}
+
+
+/// An upper bound on the instructions `compile` appends for `node`, and on the calls it takes to do so
+///
+/// Nodes without instructions count as one, so that repeating an empty group (`((){99999}){99999}`) is as
+/// large as repeating a character
+fn size(node: &Node) -> usize {
+	match node {
+		Node::Empty | Node::Char(_) | Node::Any | Node::Set(_) | Node::Start | Node::End => 1,
+		Node::Concat(nodes) => nodes.iter().fold(1, |total, node| total.saturating_add(size(node))),
+		Node::Alternation(branches) => branches.iter().fold(1, |total, branch| total.saturating_add(size(branch).saturating_add(2))),
+		// Every copy, plus the loop or the optional copies, each with at most two extra instructions
+		Node::Repeat { node, min, max } => max.unwrap_or(min.saturating_add(1)).saturating_mul(size(node).saturating_add(2)).saturating_add(1),
+	}
+}
+
+
+/// Append the instructions of `node` to `program`, which `size` has checked are not too many
+fn compile(node: &Node, program: &mut Vec<Inst>) {
+	match node {
+		Node::Empty => {},
+		Node::Char(c) => program.push(Inst::Char(*c)),
+		Node::Any => program.push(Inst::Any),
+		Node::Set(set) => program.push(Inst::Set(set.clone())),
+		Node::Start => program.push(Inst::Start),
+		Node::End => program.push(Inst::End),
+		Node::Concat(nodes) => for node in nodes {
+			compile(node, program);
+		},
+		Node::Alternation(branches) => {
+			// Every branch but the last is `Split(branch, rest)`, followed by a jump past the others
+			let mut jumps = Vec::new();
+
+			for (i, branch) in branches.iter().enumerate() {
+				if i + 1 == branches.len() {
+					compile(branch, program);
+				} else {
+					let split = program.len();
+					program.push(Inst::Split(split + 1, 0));
+					compile(branch, program);
+					jumps.push(program.len());
+					program.push(Inst::Jump(0));
+					program[split] = Inst::Split(split + 1, program.len());
+				}
+			}
+
+			let end = program.len();
+			for jump in jumps {
+				program[jump] = Inst::Jump(end);
+			}
+		},
+		Node::Repeat { node, min, max } => {
+			for _ in 0..*min {
+				compile(node, program);
+			}
+
+			match max {
+				// `Split(body, end)`, the body, then a jump back to the split
+				None => {
+					let split = program.len();
+					program.push(Inst::Split(split + 1, 0));
+					compile(node, program);
+					program.push(Inst::Jump(split));
+					program[split] = Inst::Split(split + 1, program.len());
+				},
+				// Every optional copy is `Split(body, end)` followed by the body
+				Some(max) => {
+					let mut splits = Vec::new();
+
+					for _ in *min..*max {
+						splits.push(program.len());
+						program.push(Inst::Split(0, 0));
+						compile(node, program);
+					}
+
+					let end = program.len();
+					for split in splits {
+						program[split] = Inst::Split(split + 1, end);
+					}
+				},
+			}
+		},
+	}
+}
+
+
+/// The instructions a set of threads is at, in the order they were added, without duplicates
+struct Threads {
+	dense: Vec<usize>,
+	/// The index of every instruction in `dense`, valid only when it points back to it
+	sparse: Vec<usize>,
+	len: usize,
+}
+
+impl Threads {
+	fn new(size: usize) -> Threads {
+		Threads{
+			dense: vec![0; size],
+			sparse: vec![0; size],
+			len: 0,
+		}
+	}
+
+	fn clear(&mut self) {
+		self.len = 0;
+	}
+
+	/// Add `pc`, returning `false` when it was already there
+	fn insert(&mut self, pc: usize) -> bool {
+		let i = self.sparse[pc];
+
+		if i < self.len && self.dense[i] == pc {
+			return false;
+		}
+
+		self.dense[self.len] = pc;
+		self.sparse[pc] = self.len;
+		self.len += 1;
+		true
+	}
+}
+
+
+struct Parser<'a> {
+	pattern: &'a str,
+	chars: Vec<char>,
+	pos: usize,
+	depth: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn peek(&self) -> Option<char> {
+		self.chars.get(self.pos).copied()
+	}
+
+	fn bump(&mut self) -> Option<char> {
+		let c = self.peek()?;
+		self.pos += 1;
+		Some(c)
+	}
+
+	fn error(&self, msg: &str) -> Error {
+		Error::InvalidPattern(format!("{} at position {} in `{}`", msg, self.pos, self.pattern))
+	}
+
+	/// Go one group or repetition deeper
+	fn nest(&mut self) -> Result<(), Error> {
+		self.depth += 1;
+
+		if self.depth > MAX_DEPTH {
+			return Err(self.error(&format!("nested deeper than {} levels", MAX_DEPTH)));
+		}
+
+		Ok(())
+	}
+
+	fn parse_alternation(&mut self) -> Result<Node, Error> {
+		let mut branches = vec![self.parse_concat()?];
+
+		while self.peek() == Some('|') {
+			self.bump();
+			branches.push(self.parse_concat()?);
+		}
+
+		if branches.len() == 1 {
+			Ok(branches.remove(0))
+		} else {
+			Ok(Node::Alternation(branches))
+		}
+	}
+
+	fn parse_concat(&mut self) -> Result<Node, Error> {
+		let mut nodes = Vec::new();
+
+		while let Some(c) = self.peek() {
+			match c {
+				'|' | ')' => break,
+				'*' | '+' | '?' | '{' => return Err(self.error("repetition without an expression")),
+				_ => {
+					let atom = self.parse_atom()?;
+					nodes.push(self.parse_repetition(atom)?);
+				},
+			}
+		}
+
+		match nodes.len() {
+			0 => Ok(Node::Empty),
+			1 => Ok(nodes.remove(0)),
+			_ => Ok(Node::Concat(nodes)),
+		}
+	}
+
+	fn parse_atom(&mut self) -> Result<Node, Error> {
+		match self.bump() {
+			Some('(') => {
+				if self.peek() == Some('?') {
+					self.bump();
+					if self.bump() != Some(':') {
+						return Err(self.error("unsupported group flags"));
+					}
+				}
+
+				self.nest()?;
+				let node = self.parse_alternation()?;
+				self.depth -= 1;
+
+				match self.bump() {
+					Some(')') => Ok(node),
+					_ => Err(self.error("unclosed `(`")),
+				}
+			},
+			Some('[') => self.parse_set(),
+			Some('.') => Ok(Node::Any),
+			Some('^') => Ok(Node::Start),
+			Some('$') => Ok(Node::End),
+			Some('\\') => match self.parse_escape()? {
+				Escape::Char(c) => Ok(Node::Char(c)),
+				Escape::Class(class, negated) => Ok(Node::Set(Set{ negated: false, ranges: Vec::new(), classes: vec![(class, negated)] })),
+			},
+			Some(c) => Ok(Node::Char(c)),
+			None => Err(self.error("unexpected end of pattern")),
+		}
+	}
+
+	fn parse_escape(&mut self) -> Result<Escape, Error> {
+		match self.bump() {
+			Some('d') => Ok(Escape::Class(Perl::Digit, false)),
+			Some('D') => Ok(Escape::Class(Perl::Digit, true)),
+			Some('w') => Ok(Escape::Class(Perl::Word, false)),
+			Some('W') => Ok(Escape::Class(Perl::Word, true)),
+			Some('s') => Ok(Escape::Class(Perl::Space, false)),
+			Some('S') => Ok(Escape::Class(Perl::Space, true)),
+			Some('n') => Ok(Escape::Char('\n')),
+			Some('r') => Ok(Escape::Char('\r')),
+			Some('t') => Ok(Escape::Char('\t')),
+			Some(c) if !c.is_alphanumeric() => Ok(Escape::Char(c)),
+			Some(_) => Err(self.error("unsupported escape")),
+			None => Err(self.error("trailing `\\`")),
+		}
+	}
+
+	fn parse_set(&mut self) -> Result<Node, Error> {
+		let mut set = Set{ negated: false, ranges: Vec::new(), classes: Vec::new() };
+
+		if self.peek() == Some('^') {
+			self.bump();
+			set.negated = true;
+		}
+
+		// A `]` right after the opening bracket is part of the set
+		let mut first = true;
+
+		loop {
+			let lo = match self.bump() {
+				Some(']') if !first => return Ok(Node::Set(set)),
+				Some('\\') => match self.parse_escape()? {
+					Escape::Char(c) => c,
+					Escape::Class(class, negated) => {
+						set.classes.push((class, negated));
+						first = false;
+						continue;
+					},
+				},
+				Some(c) => c,
+				None => return Err(self.error("unclosed `[`")),
+			};
+			first = false;
+
+			if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|c| *c != ']') {
+				self.bump();
+
+				let hi = match self.bump() {
+					Some('\\') => match self.parse_escape()? {
+						Escape::Char(c) => c,
+						Escape::Class(..) => return Err(self.error("invalid range")),
+					},
+					Some(c) => c,
+					None => return Err(self.error("unclosed `[`")),
+				};
+
+				if hi < lo {
+					return Err(self.error("invalid range"));
+				}
+
+				set.ranges.push((lo, hi));
+			} else {
+				set.ranges.push((lo, lo));
+			}
+		}
+	}
+
+	fn parse_repetition(&mut self, mut atom: Node) -> Result<Node, Error> {
+		// Every repetition of a repetition (`a{2}{2}`) wraps the atom once more
+		let depth = self.depth;
+
+		loop {
+			let (min, max) = match self.peek() {
+				Some('*') => { self.bump(); (0, None) },
+				Some('+') => { self.bump(); (1, None) },
+				Some('?') => { self.bump(); (0, Some(1)) },
+				Some('{') => { self.bump(); self.parse_counted()? },
+				_ => {
+					self.depth = depth;
+					return Ok(atom);
+				},
+			};
+
+			// Lazy, which only changes what a capture would hold
+			if self.peek() == Some('?') {
+				self.bump();
+			}
+
+			if let Node::Start | Node::End = atom {
+				return Err(self.error("repetition of an anchor"));
+			}
+
+			self.nest()?;
+			atom = Node::Repeat{ node: Box::new(atom), min, max };
+		}
+	}
+
+	/// Parse the inside of `{n}`, `{n,}` or `{n,m}` (the `{` is already consumed)
+	fn parse_counted(&mut self) -> Result<(usize, Option<usize>), Error> {
+		let min = match self.parse_number() {
+			Some(n) => n,
+			None => return Err(self.error("invalid repetition count")),
+		};
+
+		let max = match self.bump() {
+			Some('}') => return Ok((min, Some(min))),
+			Some(',') => if self.peek() == Some('}') { None } else { self.parse_number() },
+			_ => return Err(self.error("invalid repetition count")),
+		};
+
+		match (self.bump(), max) {
+			(Some('}'), None) => Ok((min, None)),
+			(Some('}'), Some(max)) if max >= min => Ok((min, Some(max))),
+			_ => Err(self.error("invalid repetition count")),
+		}
+	}
+
+	fn parse_number(&mut self) -> Option<usize> {
+		let start = self.pos;
+
+		while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+			self.bump();
+		}
+
+		self.chars[start..self.pos].iter().collect::<String>().parse().ok()
+	}
+}
+
+enum Escape {
+	Char(char),
+	Class(Perl, bool),
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::Regex;
+
+	fn matches(pattern: &str, text: &str) -> bool {
+		Regex::new(pattern).unwrap().is_match(text)
+	}
+
+	#[test]
+	fn literals_match_anywhere_unless_anchored() {
+		assert!(matches("conf", "app.config"));
+		assert!(!matches("^conf", "app.config"));
+		assert!(matches("^app\\.conf", "app.config"));
+		assert!(!matches("^app\\.conf", "appXconfig"));
+		assert!(matches("g$", "app.config.bak.g"));
+		assert!(!matches("^$", "a"));
+		assert!(matches("^$", ""));
+		assert!(matches("", "anything"));
+	}
+
+	#[test]
+	fn classes_and_sets() {
+		assert!(matches("^backup_\\d{4}-\\d{2}-\\d{2}\\.tar$", "backup_2024-01-31.tar"));
+		assert!(!matches("^backup_\\d{4}-\\d{2}-\\d{2}\\.tar$", "backup_2024-1-31.tar"));
+		assert!(matches("^\\w+$", "snake_case_9"));
+		assert!(!matches("^\\w+$", "kebab-case"));
+		assert!(matches("^\\S\\s\\D$", "a b"));
+		assert!(matches("^[a-c]+[^a-c]$", "abcabcz"));
+		assert!(!matches("^[a-c]+[^a-c]$", "abcabca"));
+		assert!(matches("^[]a]+$", "]a]"));
+		assert!(matches("^[a-]+$", "a-a"));
+		assert!(matches("^[\\d.]+$", "1.2.3"));
+		assert!(matches("^.$", "é"));
+		assert!(!matches("^.$", "\n"));
+	}
+
+	#[test]
+	fn alternation_and_groups() {
+		assert!(matches("^(jpe?g|png)$", "jpg"));
+		assert!(matches("^(jpe?g|png)$", "jpeg"));
+		assert!(matches("^(?:jpe?g|png)$", "png"));
+		assert!(!matches("^(jpe?g|png)$", "gif"));
+		assert!(matches("^(a|)b$", "b"));
+		assert!(matches("^x(a|b|c)*y$", "xabccbay"));
+	}
+
+	#[test]
+	fn repetitions() {
+		assert!(matches("^a{3}$", "aaa"));
+		assert!(!matches("^a{3}$", "aa"));
+		assert!(!matches("^a{3}$", "aaaa"));
+		assert!(matches("^a{2,}$", "aaaaa"));
+		assert!(!matches("^a{2,}$", "a"));
+		assert!(matches("^a{1,2}b$", "aab"));
+		assert!(!matches("^a{1,2}b$", "aaab"));
+		assert!(matches("^a+?b*?$", "aaabb"));
+		assert!(matches("^(a*)*$", "aaaa"));
+		assert!(matches("^(a?)*b$", "b"));
+		assert!(matches("^(|a)+$", ""));
+	}
+
+	#[test]
+	fn nested_repetitions_run_in_linear_time() {
+		// Exponential for a backtracking matcher
+		let name = format!("{}b.txt", "a".repeat(35));
+		assert!(!matches("(a*)*c", &name));
+		assert!(!matches("^(a|a)*c", &name));
+		assert!(!matches("^(a|aa)+$", &"a".repeat(10_000).replace("aaaa", "aaab")));
+
+		let text = "a".repeat(30);
+		assert!(matches(&format!("^{}{}$", "a?".repeat(30), "a".repeat(30)), &text));
+		assert!(matches("^(a?){30}a{30}$", &text));
+	}
+
+	#[test]
+	fn invalid_patterns_are_rejected() {
+		for pattern in ["(a", "a)", "[ab", "*a", "a{", "a{2,1}", "a{x}", "[z-a]", "^*", "\\", "\\q", "(?i)a"] {
+			assert!(Regex::new(pattern).is_err(), "{}", pattern);
+		}
+	}
+
+	#[test]
+	fn huge_repetitions_are_rejected() {
+		assert!(Regex::new("(a{1000}){1000}").is_err());
+		assert!(Regex::new("(){4000000000}").is_err());
+		assert!(Regex::new("((){99999}){99999}").is_err());
+		assert!(Regex::new("(a{100}){100}").is_ok());
+	}
+
+	#[test]
+	fn deep_nesting_is_rejected() {
+		assert!(Regex::new(&"(".repeat(200_000)).is_err());
+		assert!(Regex::new(&format!("{}a{}", "(".repeat(200_000), ")".repeat(200_000))).is_err());
+		assert!(Regex::new(&format!("a{}", "{1}".repeat(200_000))).is_err());
+		assert!(matches(&format!("^{}a{}$", "(".repeat(64), ")".repeat(64)), "a"));
+	}
+}
@@ -0,0 +1,804 @@
+//! The directory walk shared by every search, driven by `FindOptions`
+
+use crate::glob::Pattern;
+#[cfg(feature = "ignore")]
+use crate::ignore::{self, IgnoreList};
+use crate::{EntryErrors, EntryKind, Error, FindOptions, ProcessReport, SkipReason, SortOrder, SymlinkPolicy, TraceEvent};
+
+use std::borrow::Cow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashSet;
+use std::fs::{DirEntry, Metadata};
+use std::io::Read;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+
+
+/// The hook told about every entry examined, with their count so far and their total when it is known
+pub(crate) type Progress<'a> = &'a mut dyn FnMut(usize, Option<usize>);
+
+
+/// Walk the directory of `options`, calling `process` on every entry it selects and `progress` on every entry examined
+///
+/// Returns the number of entries processed, or `MissingFiles` when requested filenames were not found.
+/// Nothing is reported missing when `process` stops the walk early
+pub(crate) fn run(options: &FindOptions, progress: Option<Progress>, process: &mut dyn FnMut(&PathBuf) -> Result<ControlFlow<()>, Error>) -> Result<usize, Error> {
+	let mut search = Search::new(options.clone(), false, progress.is_some());
+	let mut ignored = |_, _| ();
+	let progress: Progress = match progress {
+		Some(progress) => progress,
+		None => &mut ignored,
+	};
+	let mut count = 0;
+
+	while let Some(next) = search.next_entry(progress) {
+		let path = next?;
+		count += 1;
+
+		if options.dry_run {
+			continue;
+		}
+
+		if process(&path)?.is_break() {
+			break;
+		}
+	}
+
+	Ok(count)
+}
+
+/// Walk the directory of `options` like `run`, but record the failures limited to a single entry
+/// (including those returned by `process`) along with its path instead of stopping at the first one
+///
+/// Missing filenames are reported apart from the failures. Failures that concern the whole search
+/// (the directory itself, invalid patterns, `SymlinkPolicy::Error`) are still returned
+pub(crate) fn run_collecting<T>(options: &FindOptions, process: &mut dyn FnMut(&PathBuf) -> Result<T, Error>) -> Result<ProcessReport<T>, Error> {
+	let mut search = Search::new(options.clone(), true, false);
+	let mut report = ProcessReport{ processed: Vec::new(), skipped: 0, errors: Vec::new(), missing: Vec::new() };
+	let mut examined = 0;
+	let mut selected = 0;
+
+	while let Some(next) = search.next_entry(&mut |count, _| examined = count) {
+		match next {
+			Ok(path) => {
+				selected += 1;
+
+				if options.dry_run {
+					continue;
+				}
+
+				match process(&path) {
+					Ok(result) => report.processed.push(result),
+					Err(err) => search.walk.record(path, err),
+				}
+			},
+			Err(Error::MissingFiles(missing)) => report.missing = missing,
+			Err(err) => return Err(err),
+		}
+	}
+
+	report.skipped = examined - selected;
+	report.errors = search.walk.errors.take().unwrap_or_default();
+	Ok(report)
+}
+
+
+/// A search walking its directory one selected entry at a time, as driven by `run`, `run_collecting`
+/// or a `FileFinder`
+pub(crate) struct Search {
+	options: FindOptions,
+	walk: Walk,
+	started: bool,
+	finished: bool,
+}
+
+impl Search {
+	/// A search with the given `options` that has not touched the filesystem yet
+	///
+	/// `collect_errors` records the failures limited to one entry instead of returning them, and
+	/// `progress` lists each directory of a non-recursive search up front to know its total
+	pub(crate) fn new(options: FindOptions, collect_errors: bool, progress: bool) -> Search {
+		let list_totals = progress && !options.recursive;
+
+		Search{
+			options,
+			walk: Walk{
+				wanted: None,
+				found: HashSet::new(),
+				globs: None,
+				exclude: None,
+				#[cfg(feature = "regex")]
+				regex: None,
+				now: SystemTime::now(),
+				deadline: None,
+				root: None,
+				#[cfg(feature = "ignore")]
+				lists: Vec::new(),
+				visited: HashSet::new(),
+				errors: if collect_errors { Some(Vec::new()) } else { None },
+				examined: 0,
+				list_totals,
+				stack: Vec::new(),
+				descend: None,
+				metadata: None,
+				returned: None,
+			},
+			started: false,
+			finished: false,
+		}
+	}
+
+	/// The next entry selected, or the error stopping the search, calling `progress` on every entry examined
+	///
+	/// Once the walk is over, missing filenames are reported as `MissingFiles`. Nothing follows an error
+	pub(crate) fn next_entry(&mut self, progress: Progress) -> Option<Result<PathBuf, Error>> {
+		if self.finished {
+			return None;
+		}
+
+		let next = if self.started {
+			self.walk.next_entry(&self.options, progress)
+		} else {
+			self.started = true;
+			self.walk.start(&self.options).and_then(|_| self.walk.next_entry(&self.options, progress))
+		};
+
+		match next {
+			Ok(Some(path)) => Some(Ok(path)),
+			Ok(None) => {
+				self.finished = true;
+
+				let mut missing = self.walk.missing(&self.options);
+				if self.options.fail_fast {
+					missing.truncate(1);
+				}
+
+				if missing.is_empty() { None } else { Some(Err(Error::MissingFiles(missing))) }
+			},
+			Err(err) => {
+				self.finished = true;
+				Some(Err(err))
+			},
+		}
+	}
+
+	/// The metadata of the entry last returned by `next_entry`, following symlinks, when it could be read.
+	/// It is read the first time it is asked for, unless the criteria already needed it
+	pub(crate) fn metadata(&mut self) -> Option<&Metadata> {
+		let returned = self.walk.returned.take()?;
+		self.walk.metadata(&returned);
+		self.walk.returned = Some(returned);
+
+		self.walk.metadata.as_ref().and_then(Option::as_ref)
+	}
+}
+
+
+/// A directory being listed
+struct Frame {
+	directory: PathBuf,
+	entries: Box<dyn Iterator<Item = std::io::Result<DirEntry>> + Send>,
+	/// How many levels below the searched directory it is
+	depth: usize,
+	/// The number of entries in the directory, when it was listed up front
+	total: Option<usize>,
+	/// The number of ignore lists applying to its parent, restored once it has been walked
+	#[cfg(feature = "ignore")]
+	inherited: usize,
+}
+
+/// The state of a running search
+struct Walk {
+	/// Keys of the requested filenames (see `key`)
+	wanted: Option<HashSet<String>>,
+	found: HashSet<String>,
+	globs: Option<Vec<Pattern>>,
+	exclude: Option<Vec<Pattern>>,
+	#[cfg(feature = "regex")]
+	regex: Option<crate::regex::Regex>,
+	/// Ages are measured against the time the search started
+	now: SystemTime,
+	/// When the search times out, from the time it started (see `FindOptions::timeout`)
+	deadline: Option<Instant>,
+	/// The directory the paths selected are made relative to (see `FindOptions::relative`)
+	root: Option<PathBuf>,
+	/// The ignore files applying to the directory being walked, from the lowest to the highest precedence
+	#[cfg(feature = "ignore")]
+	lists: Vec<IgnoreList>,
+	/// Canonical paths of the directories walked, so a symlink pointing back up the tree is only walked once
+	visited: HashSet<PathBuf>,
+	/// The failures recorded instead of returned, when collecting them (see `run_collecting`)
+	errors: Option<EntryErrors>,
+	/// The number of entries examined so far, processed or not
+	examined: usize,
+	list_totals: bool,
+	/// The directories being listed, the deepest last
+	stack: Vec<Frame>,
+	/// The directory to walk before the next entry, the last one returned, along with its depth.
+	/// It is only entered when asked for the entry after it, so an early stop never lists it
+	descend: Option<(PathBuf, usize)>,
+	/// The metadata of the last entry examined, following symlinks. `None` until it is needed (see `Walk::metadata`),
+	/// then `Some(None)` when it could not be read
+	metadata: Option<Option<Metadata>>,
+	/// The last entry returned, whose metadata is read when `Search::metadata` asks for it
+	returned: Option<DirEntry>,
+}
+
+impl Walk {
+	/// Compile the criteria of `options`, check its directory and enter it
+	///
+	/// # Errors
+	///   `InvalidPattern(String)` - A glob pattern or the regular expression could not be compiled
+	fn start(&mut self, options: &FindOptions) -> Result<(), Error> {
+		// A timeout too long to be represented never expires
+		self.deadline = options.timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+
+		if options.relative {
+			let directory = &options.directory;
+			self.root = Some(if options.canonicalize { directory.canonicalize().unwrap_or_else(|_| directory.clone()) } else { directory.clone() });
+		}
+
+		if let Some(patterns) = &options.globs {
+			self.globs = Some(patterns.iter().map(|p| Pattern::new(p)).collect::<Result<Vec<_>, _>>()?);
+		}
+
+		if let Some(patterns) = &options.exclude {
+			self.exclude = Some(patterns.iter().map(|p| Pattern::new(p)).collect::<Result<Vec<_>, _>>()?);
+		}
+
+		#[cfg(feature = "regex")]
+		if let Some(pattern) = &options.regex {
+			self.regex = Some(crate::regex::Regex::new(pattern)?);
+		}
+
+		self.wanted = options.filenames.as_ref().map(|names| names.iter().map(|name| key(options, name)).collect());
+
+		crate::check_directory(&options.directory)?;
+
+		#[cfg(feature = "ignore")]
+		if let Some(files) = options.ignore_files {
+			if files.global {
+				if let Some(path) = ignore::global_excludes_path() {
+					self.lists.extend(IgnoreList::read(&options.directory, &path)?);
+				}
+			}
+		}
+
+		self.enter(options, options.directory.clone(), 0)
+	}
+
+	/// Record `err` on `path` among the collected failures
+	fn record(&mut self, path: PathBuf, err: Error) {
+		if let Some(errors) = &mut self.errors {
+			errors.push((path, err));
+		}
+	}
+
+	/// Get past the failure `err` on `path`: record it when collecting errors, drop it with `ignore_fail`
+	/// or return it otherwise
+	fn skip(&mut self, options: &FindOptions, path: &Path, err: Error) -> Result<(), Error> {
+		skip_failure(&mut self.errors, options, path, err)
+	}
+
+	/// The metadata of `entry`, the last one examined, read the first time it is needed. Symbolic links have
+	/// theirs read by `next_entry` already, so the metadata of the entry itself is that of its target
+	fn metadata(&mut self, entry: &DirEntry) -> Option<&Metadata> {
+		self.metadata.get_or_insert_with(|| entry.metadata().ok()).as_ref()
+	}
+
+	/// Start listing `directory`, `depth` levels below the searched directory, unless it was already walked
+	fn enter(&mut self, options: &FindOptions, directory: PathBuf, depth: usize) -> Result<(), Error> {
+		if let Ok(canonical) = directory.canonicalize() {
+			if !self.visited.insert(canonical) {
+				return Ok(());
+			}
+		}
+
+		#[cfg(feature = "ignore")]
+		let inherited = self.lists.len();
+
+		// `.ignore` is pushed last so it takes precedence over `.gitignore`
+		#[cfg(feature = "ignore")]
+		if let Some(files) = options.ignore_files {
+			if files.gitignore {
+				self.lists.extend(IgnoreList::read(&directory, &directory.join(".gitignore"))?);
+			}
+			if files.dot_ignore {
+				self.lists.extend(IgnoreList::read(&directory, &directory.join(".ignore"))?);
+			}
+		}
+
+		let entries = match read_entries(&directory, options.sort) {
+			Ok(entries) => entries,
+			// An unreadable subdirectory is got past like any other failure, while the searched directory itself
+			// always has to be readable unless errors are collected
+			Err(err) if self.errors.is_some() || depth > 0 => {
+				#[cfg(feature = "ignore")]
+				self.lists.truncate(inherited);
+				return self.skip(options, &directory, err);
+			},
+			Err(err) => return Err(err),
+		};
+
+		// The total is only known by listing the directory before its first entry
+		let (entries, total) = if self.list_totals {
+			let listed = entries.collect::<Vec<_>>();
+			let total = listed.len();
+			(Box::new(listed.into_iter()) as Box<dyn Iterator<Item = _> + Send>, Some(total))
+		} else {
+			(entries, None)
+		};
+
+		self.stack.push(Frame{ directory, entries, depth, total, #[cfg(feature = "ignore")] inherited });
+
+		Ok(())
+	}
+
+	/// The next entry selected depth-first, or `None` once every directory has been walked
+	fn next_entry(&mut self, options: &FindOptions, progress: Progress) -> Result<Option<PathBuf>, Error> {
+		loop {
+			if options.fail_fast && self.wanted.as_ref().is_some_and(|wanted| self.found.len() == wanted.len()) {
+				return Ok(None);
+			}
+
+			if let Some((directory, depth)) = self.descend.take() {
+				self.enter(options, directory, depth)?;
+			}
+
+			let (entry, depth, total) = match self.stack.last_mut() {
+				Some(frame) => match frame.entries.next() {
+					Some(entry) => (entry, frame.depth, frame.total),
+					None => {
+						#[cfg(feature = "ignore")]
+						self.lists.truncate(frame.inherited);
+						self.stack.pop();
+						continue;
+					},
+				},
+				None => return Ok(None),
+			};
+
+			if options.cancellation.as_ref().is_some_and(|token| token.is_cancelled()) {
+				return Err(Error::Cancelled);
+			}
+
+			if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+				return Err(Error::TimedOut);
+			}
+
+			self.examined += 1;
+			progress(self.examined, total);
+
+			let e = match entry {
+				Ok(e) => e,
+				Err(_) => {
+					let directory = self.stack.last().map_or(Path::new(""), |frame| &frame.directory);
+					skip_failure(&mut self.errors, options, directory, Error::CouldNotOpenEntry)?;
+					continue;
+				},
+			};
+
+			let path = e.path();
+			trace(options, TraceEvent::Examined(&path));
+
+			if let Some(reason) = excluded(&e, options.symlinks, options.include_hidden)? {
+				trace(options, TraceEvent::Skipped(&path, reason));
+				continue;
+			}
+
+			// The type of the entry comes with it on most platforms, so only symbolic links are read here. They
+			// are followed, so a symlinked file is sized and dated by its target
+			self.metadata = None;
+			let is_dir = match e.file_type() {
+				Ok(file_type) if !file_type.is_symlink() => file_type.is_dir(),
+				_ => {
+					let metadata = std::fs::metadata(&path).ok();
+					let is_dir = metadata.as_ref().is_some_and(|m| m.is_dir());
+					self.metadata = Some(metadata);
+					is_dir
+				},
+			};
+
+			#[cfg(feature = "ignore")]
+			if let Some(files) = options.ignore_files {
+				if ignore::is_ignored(&self.lists, &path, is_dir) || (files.gitignore && is_dir && e.file_name() == ".git") {
+					trace(options, TraceEvent::Skipped(&path, SkipReason::Ignored));
+					continue;
+				}
+			}
+
+			let wanted_kind = match options.entry_kind {
+				EntryKind::FilesOnly => !is_dir,
+				EntryKind::DirsOnly => is_dir,
+				EntryKind::Both => true,
+			};
+
+			let rejected = if wanted_kind { self.selects(options, &e, &path, is_dir)? } else { Some(SkipReason::Kind) };
+
+			match rejected {
+				Some(reason) => trace(options, TraceEvent::Skipped(&path, reason)),
+				None => trace(options, TraceEvent::Matched(&path)),
+			}
+
+			if is_dir && options.recursive && options.max_depth.map_or(true, |max| depth < max) {
+				self.descend = Some((path.clone(), depth + 1));
+			}
+
+			if rejected.is_none() {
+				let path = if options.canonicalize {
+					match path.canonicalize() {
+						Ok(canonical) => canonical,
+						Err(err) => {
+							self.skip(options, &path, Error::IoError(err))?;
+							continue;
+						},
+					}
+				} else {
+					path
+				};
+
+				self.returned = Some(e);
+
+				return Ok(Some(match &self.root {
+					Some(root) => path.strip_prefix(root).map(Path::to_path_buf).unwrap_or(path),
+					None => path,
+				}));
+			}
+		}
+	}
+
+	/// The first criterion of the search `entry` does not match, or `None` when it matches them all,
+	/// recording it when it is a requested filename
+	fn selects(&mut self, options: &FindOptions, entry: &DirEntry, path: &Path, is_dir: bool) -> Result<Option<SkipReason>, Error> {
+		#[cfg(feature = "regex")]
+		let by_regex = self.regex.is_some();
+		#[cfg(not(feature = "regex"))]
+		let by_regex = false;
+
+		let mut name_key = None;
+		// Whether each name and extension criterion set matches, with the reason given when it does not
+		let mut outcomes = Vec::new();
+
+		if self.wanted.is_some() || self.globs.is_some() || self.exclude.is_some() || by_regex {
+			let file_name = entry.file_name();
+			let name = match file_name.to_str() {
+				Some(name) => Cow::Borrowed(name),
+				// Invalid sequences become U+FFFD, so distinct names may compare equal
+				None if options.lossy_names => file_name.to_string_lossy(),
+				None => return self.skip(options, path, Error::InvalidUnicodeData).map(|_| Some(SkipReason::Failed)),
+			};
+			let name = name.as_ref();
+
+			// Checked first, as an excluded entry is left out whatever else it matches
+			if self.exclude.as_ref().is_some_and(|patterns| patterns.iter().any(|p| p.matches(name))) {
+				return Ok(Some(SkipReason::Excluded));
+			}
+
+			// With `relative_paths` the filenames are looked up by the path relative to `directory` instead
+			let relative = if self.wanted.is_some() && options.relative_paths {
+				match relative_name(&options.directory, path, options.lossy_names) {
+					Some(relative) => Some(relative),
+					None => return self.skip(options, path, Error::InvalidUnicodeData).map(|_| Some(SkipReason::Failed)),
+				}
+			} else {
+				None
+			};
+
+			if let Some(wanted) = &self.wanted {
+				let key = key(options, relative.as_deref().unwrap_or(name));
+				let found = wanted.contains(&key);
+				if found {
+					name_key = Some(key);
+				}
+				outcomes.push((found, SkipReason::Name));
+			}
+
+			if let Some(globs) = &self.globs {
+				outcomes.push((globs.iter().any(|p| p.matches(name)), SkipReason::Name));
+			}
+
+			#[cfg(feature = "regex")]
+			if let Some(regex) = &self.regex {
+				outcomes.push((regex.is_match(name), SkipReason::Name));
+			}
+		}
+
+		if let Some(extensions) = &options.extensions {
+			outcomes.push((matching_extension(path, extensions, options.lossy_names).is_some(), SkipReason::Extension));
+		}
+
+		// With `match_any` a single criterion matching is enough, otherwise every one of them has to
+		if let Some(&(_, reason)) = outcomes.iter().find(|(matches, _)| !matches) {
+			if !(options.match_any && outcomes.iter().any(|(matches, _)| *matches)) {
+				return Ok(Some(reason));
+			}
+		}
+
+		let (min, max) = options.size;
+		let (older_than, newer_than) = options.age;
+
+		if min.is_some() || max.is_some() || older_than.is_some() || newer_than.is_some() {
+			// Sizes and ages are those of files, directories never match them
+			if is_dir {
+				return Ok(Some(SkipReason::Size));
+			}
+
+			let metadata = match self.metadata(entry) {
+				Some(metadata) => metadata.clone(),
+				// Read again when it failed, to report why
+				None => match std::fs::metadata(path) {
+					Ok(metadata) => metadata,
+					Err(err) => return self.skip(options, path, Error::IoError(err)).map(|_| Some(SkipReason::Failed)),
+				},
+			};
+
+			let size = metadata.len();
+			if !(min.map_or(true, |min| size >= min) && max.map_or(true, |max| size <= max)) {
+				return Ok(Some(SkipReason::Size));
+			}
+
+			if older_than.is_some() || newer_than.is_some() {
+				let modified = match metadata.modified() {
+					Ok(modified) => modified,
+					Err(err) => return self.skip(options, path, Error::IoError(err)).map(|_| Some(SkipReason::Failed)),
+				};
+
+				// Files modified in the future (clock skew) have an age of zero
+				let age = self.now.duration_since(modified).unwrap_or_default();
+				if !(older_than.map_or(true, |d| age > d) && newer_than.map_or(true, |d| age < d)) {
+					return Ok(Some(SkipReason::Age));
+				}
+			}
+		}
+
+		// Checked last, as it is the only criterion opening the file
+		if let Some(magic) = &options.magic {
+			if is_dir {
+				return Ok(Some(SkipReason::Magic));
+			}
+
+			match starts_with(path, magic) {
+				Ok(true) => (),
+				Ok(false) => return Ok(Some(SkipReason::Magic)),
+				Err(err) => return self.skip(options, path, Error::IoError(err)).map(|_| Some(SkipReason::Failed)),
+			}
+		}
+
+		if let Some(key) = name_key {
+			self.found.insert(key);
+		}
+
+		Ok(None)
+	}
+
+	/// The requested filenames that were not found, each reported once and in the requested order
+	fn missing(&self, options: &FindOptions) -> Vec<String> {
+		let names = match &options.filenames {
+			Some(names) => names,
+			None => return Vec::new(),
+		};
+
+		let mut reported = HashSet::new();
+
+		names.iter().filter(|name| {
+			let key = key(options, name);
+			!self.found.contains(&key) && reported.insert(key)
+		}).cloned().collect()
+	}
+}
+
+
+/// `Walk::skip` borrowing only the recorded failures of the walk, so `path` can be borrowed from the rest of it.
+/// `path` is only copied when the failure is recorded
+fn skip_failure(errors: &mut Option<EntryErrors>, options: &FindOptions, path: &Path, err: Error) -> Result<(), Error> {
+	match errors {
+		Some(errors) => errors.push((path.to_path_buf(), err)),
+		None => if !options.ignore_fail { return Err(err) },
+	}
+
+	Ok(())
+}
+
+
+/// Hand `event` to the trace hook of `options`, if it has one
+fn trace(options: &FindOptions, event: TraceEvent) {
+	if let Some(hook) = options.trace {
+		hook(&event);
+	}
+}
+
+
+/// The key a filename is looked up with
+///
+/// Duplicates (or names only differing in ASCII case with `case_insensitive`) share the same key,
+/// so they are looked up once and found together
+fn key(options: &FindOptions, name: &str) -> String {
+	if options.case_insensitive {
+		name.to_ascii_lowercase()
+	} else {
+		name.to_string()
+	}
+}
+
+
+/// The path of `path` relative to `directory`, with its components joined by `/` on every platform
+///
+/// `None` when a component is not valid UTF-8, unless it is converted `lossy`
+fn relative_name(directory: &Path, path: &Path, lossy: bool) -> Option<String> {
+	let relative = path.strip_prefix(directory).ok()?;
+	let components = relative.iter()
+		.map(|c| match c.to_str() {
+			Some(c) => Some(Cow::Borrowed(c)),
+			None if lossy => Some(c.to_string_lossy()),
+			None => None,
+		})
+		.collect::<Option<Vec<_>>>()?;
+
+	Some(components.join("/"))
+}
+
+
+/// The longest of the requested `extensions` that `path` has, as it was requested, the first one among equals
+///
+/// An extension containing a dot (`tar.gz`) is compared with the trailing components of the name, which has to
+/// start with something else (`.tar.gz` alone has the extension `gz`). `None` when the name is not valid UTF-8,
+/// unless it is converted `lossy`
+pub(crate) fn matching_extension<'a>(path: &Path, extensions: &'a [String], lossy: bool) -> Option<&'a str> {
+	let name = path.file_name().and_then(|name| match name.to_str() {
+		Some(name) => Some(Cow::Borrowed(name)),
+		None if lossy => Some(name.to_string_lossy()),
+		None => None,
+	})?;
+	let name = name.as_bytes();
+
+	// `.jpg` is accepted for `jpg`
+	let stripped = |requested: &'a str| requested.strip_prefix('.').unwrap_or(requested);
+
+	// `photo.JPG` has the extension `jpg` as well, and `archive.tar.gz` has both `gz` and `tar.gz`
+	let matches = |requested: &str| match name.len().checked_sub(requested.len() + 1) {
+		Some(dot) if dot > 0 => name[dot] == b'.' && name[dot + 1..].eq_ignore_ascii_case(requested.as_bytes()),
+		_ => false,
+	};
+
+	extensions.iter()
+		.filter(|requested| matches(stripped(requested)))
+		.fold(None, |longest: Option<&'a String>, requested| match longest {
+			Some(longest) if stripped(longest).len() >= stripped(requested).len() => Some(longest),
+			_ => Some(requested),
+		})
+		.map(String::as_str)
+}
+
+
+/// Whether the contents of the file at `path` start with `magic`, reading no more than its length
+fn starts_with(path: &Path, magic: &[u8]) -> std::io::Result<bool> {
+	let mut head = Vec::with_capacity(magic.len());
+	std::fs::File::open(path)?.take(magic.len() as u64).read_to_end(&mut head)?;
+
+	Ok(head == magic)
+}
+
+
+/// Why `entry` is left out of the walk with the given `symlinks` policy and `include_hidden` setting, if it is
+///
+/// Only symbolic links can be left out (`SymlinkPolicy::Skip`) or rejected (`SymlinkPolicy::Error`),
+/// and hidden entries are left out unless `include_hidden` is set
+fn excluded(entry: &DirEntry, symlinks: SymlinkPolicy, include_hidden: bool) -> Result<Option<SkipReason>, Error> {
+	if !include_hidden && is_hidden(entry) {
+		return Ok(Some(SkipReason::Hidden));
+	}
+
+	if !entry.file_type().is_ok_and(|t| t.is_symlink()) {
+		return Ok(None);
+	}
+
+	match symlinks {
+		SymlinkPolicy::Follow => Ok(None),
+		SymlinkPolicy::Skip => Ok(Some(SkipReason::Symlink)),
+		SymlinkPolicy::Error => Err(Error::UnexpectedSymlink(entry.path())),
+	}
+}
+
+/// Whether the name of `entry` starts with a `.`, or on Windows with the `hidden_attribute` feature,
+/// whether it has the hidden attribute
+fn is_hidden(entry: &DirEntry) -> bool {
+	#[cfg(all(windows, feature = "hidden_attribute"))]
+	{
+		use std::os::windows::fs::MetadataExt;
+
+		const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+
+		if entry.metadata().is_ok_and(|m| m.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0) {
+			return true;
+		}
+	}
+
+	entry.file_name().to_string_lossy().starts_with('.')
+}
+
+
+/// The entries of `directory`, in the iteration order given by `sort`
+///
+/// Without an order the entries are streamed as `read_dir` yields them. Otherwise they are all collected
+/// and sorted first, with the entries that could not be read placed before the rest
+fn read_entries(directory: &Path, sort: Option<SortOrder>) -> Result<Box<dyn Iterator<Item = std::io::Result<DirEntry>> + Send>, Error> {
+	let entries = std::fs::read_dir(directory).map_err(Error::IoError)?;
+
+	let order = match sort {
+		Some(order) => order,
+		None => return Ok(Box::new(entries)),
+	};
+
+	let mut found = Vec::new();
+	let mut failed = Vec::new();
+
+	for entry in entries {
+		match entry {
+			Ok(e) => found.push(e),
+			Err(err) => failed.push(Err(err)),
+		}
+	}
+
+	match order {
+		SortOrder::NameAsc => found.sort_by_key(|e| e.file_name()),
+		SortOrder::NameDesc => found.sort_by_key(|e| Reverse(e.file_name())),
+		// Whatever the direction, ties are ordered by ascending name and entries whose date cannot be read
+		// come last
+		SortOrder::ModifiedAsc | SortOrder::ModifiedDesc => {
+			let mut dated: Vec<_> = found.into_iter().map(|e| (std::fs::metadata(e.path()).and_then(|m| m.modified()).ok(), e.file_name(), e)).collect();
+
+			dated.sort_by(|(a_date, a_name, _), (b_date, b_name, _)| {
+				let by_date = match (a_date, b_date) {
+					(Some(a_date), Some(b_date)) if order == SortOrder::ModifiedAsc => a_date.cmp(b_date),
+					(Some(a_date), Some(b_date)) => b_date.cmp(a_date),
+					(Some(_), None) => Ordering::Less,
+					(None, Some(_)) => Ordering::Greater,
+					(None, None) => Ordering::Equal,
+				};
+
+				by_date.then_with(|| a_name.cmp(b_name))
+			});
+
+			found = dated.into_iter().map(|(_, _, e)| e).collect();
+		},
+	}
+
+	Ok(Box::new(failed.into_iter().chain(found.into_iter().map(Ok))))
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::{key, matching_extension};
+	use crate::FindOptions;
+
+	use std::path::Path;
+
+	#[test]
+	fn keys_ignore_ascii_case_when_case_insensitive() {
+		let sensitive = FindOptions::new(".");
+		let insensitive = FindOptions::new(".").case_insensitive(true);
+
+		assert_ne!(key(&sensitive, "README.md"), key(&sensitive, "readme.md"));
+		assert_eq!(key(&insensitive, "README.md"), key(&insensitive, "readme.md"));
+		// Only ASCII letters are folded
+		assert_ne!(key(&insensitive, "É.txt"), key(&insensitive, "é.txt"));
+	}
+
+	fn extension(name: &str, requested: &[&str]) -> Option<String> {
+		let requested: Vec<String> = requested.iter().map(|e| e.to_string()).collect();
+		matching_extension(Path::new(name), &requested, false).map(String::from)
+	}
+
+	#[test]
+	fn extensions_ignore_ascii_case() {
+		assert_eq!(extension("photo.JPG", &["jpg"]), Some(String::from("jpg")));
+		assert_eq!(extension("photo.jpg", &["JPG"]), Some(String::from("JPG")));
+		assert_eq!(extension("photo.JpEg", &["png", "jpeg"]), Some(String::from("jpeg")));
+		assert_eq!(extension("photo.jpg", &["png"]), None);
+		// Not folded outside of ASCII
+		assert_eq!(extension("a.É", &["é"]), None);
+	}
+}
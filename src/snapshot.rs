@@ -0,0 +1,144 @@
+//! Encoding and decoding of `FileModify` records into the `SaveFileFormat`s
+//! enabled through cargo features
+
+#[cfg(feature = "bincode")]
+mod bincode;
+#[cfg(feature = "json")]
+pub(crate) mod json;
+#[cfg(feature = "ron")]
+mod ron;
+#[cfg(feature = "toml")]
+mod toml;
+
+use crate::{Error, FileModify, SaveFileFormat};
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+
+
+/// Split a date into whole seconds and nanoseconds since the Unix epoch
+///
+/// This is the portable representation every format stores
+pub(crate) fn to_unix(date: SystemTime) -> Result<(u64, u32), Error> {
+	match date.duration_since(UNIX_EPOCH) {
+		Ok(d) => Ok((d.as_secs(), d.subsec_nanos())),
+		Err(_) => Err(Error::InvalidSnapshot(String::from("date is earlier than the Unix epoch"))),
+	}
+}
+
+/// Rebuild a date from whole seconds and nanoseconds since the Unix epoch
+pub(crate) fn from_unix(secs: u64, nanos: u32) -> Result<SystemTime, Error> {
+	if nanos >= 1_000_000_000 {
+		return Err(Error::InvalidSnapshot(format!("nanoseconds out of range: {}", nanos)));
+	}
+
+	match UNIX_EPOCH.checked_add(Duration::new(secs, nanos)) {
+		Some(date) => Ok(date),
+		None => Err(Error::InvalidSnapshot(format!("date out of range: {} seconds", secs))),
+	}
+}
+
+
+/// Encode a single record in the given format
+#[cfg_attr(not(any(feature = "json", feature = "bincode", feature = "toml", feature = "ron")), allow(unused_variables))]
+pub(crate) fn encode(record: &FileModify, format: SaveFileFormat) -> Result<Vec<u8>, Error> {
+	match format {
+		#[cfg(feature = "json")]
+		SaveFileFormat::JSON => Ok(json::encode(record)?.into_bytes()),
+		#[cfg(feature = "bincode")]
+		SaveFileFormat::Bincode => bincode::encode(record),
+		#[cfg(feature = "toml")]
+		SaveFileFormat::TOML => Ok(toml::encode(record)?.into_bytes()),
+		#[cfg(feature = "ron")]
+		SaveFileFormat::RON => Ok(ron::encode(record)?.into_bytes()),
+		_ => Err(Error::UnsupportedFormat(format)),
+	}
+}
+
+/// Decode a single record from the given format
+#[cfg_attr(not(any(feature = "json", feature = "bincode", feature = "toml", feature = "ron")), allow(unused_variables))]
+pub(crate) fn decode(bytes: &[u8], format: SaveFileFormat) -> Result<FileModify, Error> {
+	match format {
+		#[cfg(feature = "json")]
+		SaveFileFormat::JSON => json::decode(text(bytes)?),
+		#[cfg(feature = "bincode")]
+		SaveFileFormat::Bincode => bincode::decode(bytes),
+		#[cfg(feature = "toml")]
+		SaveFileFormat::TOML => toml::decode(text(bytes)?),
+		#[cfg(feature = "ron")]
+		SaveFileFormat::RON => ron::decode(text(bytes)?),
+		_ => Err(Error::UnsupportedFormat(format)),
+	}
+}
+
+/// Encode a list of records in the given format
+#[cfg_attr(not(any(feature = "json", feature = "bincode", feature = "toml", feature = "ron")), allow(unused_variables))]
+pub(crate) fn encode_all(records: &[FileModify], format: SaveFileFormat) -> Result<Vec<u8>, Error> {
+	match format {
+		#[cfg(feature = "json")]
+		SaveFileFormat::JSON => Ok(json::encode_all(records)?.into_bytes()),
+		#[cfg(feature = "bincode")]
+		SaveFileFormat::Bincode => bincode::encode_all(records),
+		#[cfg(feature = "toml")]
+		SaveFileFormat::TOML => Ok(toml::encode_all(records)?.into_bytes()),
+		#[cfg(feature = "ron")]
+		SaveFileFormat::RON => Ok(ron::encode_all(records)?.into_bytes()),
+		_ => Err(Error::UnsupportedFormat(format)),
+	}
+}
+
+/// Decode a list of records from the given format
+#[cfg_attr(not(any(feature = "json", feature = "bincode", feature = "toml", feature = "ron")), allow(unused_variables))]
+pub(crate) fn decode_all(bytes: &[u8], format: SaveFileFormat) -> Result<Vec<FileModify>, Error> {
+	match format {
+		#[cfg(feature = "json")]
+		SaveFileFormat::JSON => json::decode_all(text(bytes)?),
+		#[cfg(feature = "bincode")]
+		SaveFileFormat::Bincode => bincode::decode_all(bytes),
+		#[cfg(feature = "toml")]
+		SaveFileFormat::TOML => toml::decode_all(text(bytes)?),
+		#[cfg(feature = "ron")]
+		SaveFileFormat::RON => ron::decode_all(text(bytes)?),
+		_ => Err(Error::UnsupportedFormat(format)),
+	}
+}
+
+/// The contents of a text format
+#[cfg(any(feature = "json", feature = "toml", feature = "ron"))]
+fn text(bytes: &[u8]) -> Result<&str, Error> {
+	std::str::from_utf8(bytes).map_err(|_| Error::InvalidUnicodeData)
+}
+
+
+/// Write `bytes` to `path` through a temporary file renamed over it, so `path` either keeps its old
+/// contents or holds the new ones in full, even if the process dies while writing
+///
+/// The temporary file lives next to `path`, since a rename is only atomic within a filesystem,
+/// and is removed when writing it fails
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), Error> {
+	let temporary = temporary_path(path);
+
+	let written = std::fs::File::create(&temporary)
+		.and_then(|mut file| {
+			file.write_all(bytes)?;
+			// Flushed to the disk before the rename, or a crash could still leave a truncated file behind it
+			file.sync_all()
+		})
+		.and_then(|_| std::fs::rename(&temporary, path));
+
+	if let Err(err) = written {
+		let _ = std::fs::remove_file(&temporary);
+		return Err(Error::IoError(err));
+	}
+
+	Ok(())
+}
+
+/// A hidden sibling of `path`, unique to this process so concurrent savers do not share it
+fn temporary_path(path: &Path) -> PathBuf {
+	let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+
+	path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()))
+}
@@ -0,0 +1,249 @@
+//! Reader and writer for `FileModify` records in the layout of bincode 1 with its default options
+//!
+//! Integers are fixed-size and little-endian on every machine, so snapshots are portable. A record is the
+//! length of its filename as a `u64`, the UTF-8 bytes of the filename, then its date as a `u64` of seconds
+//! and a `u32` of nanoseconds since the Unix epoch, then its content hash as an `Option<String>`: a `0` byte,
+//! or a `1` byte followed by the length and UTF-8 bytes of the hash. A list of records is their count as a `u64` followed
+//! by the records
+
+use crate::{Error, FileModify};
+use super::{from_unix, to_unix};
+
+use std::convert::TryFrom;
+use std::path::PathBuf;
+
+
+
+/// The smallest encoded record, with an empty filename and no hash
+const MIN_RECORD_SIZE: usize = 8 + 8 + 4 + 1;
+
+
+/// Encode a record
+pub(crate) fn encode(record: &FileModify) -> Result<Vec<u8>, Error> {
+	let mut out = Vec::new();
+	write_record(record, &mut out)?;
+	Ok(out)
+}
+
+/// Decode a record, which has to span all of `bytes`
+pub(crate) fn decode(bytes: &[u8]) -> Result<FileModify, Error> {
+	let mut reader = Reader{ bytes, pos: 0 };
+	let record = reader.record()?;
+	reader.finish()?;
+
+	Ok(record)
+}
+
+/// Encode a list of records
+pub(crate) fn encode_all(records: &[FileModify]) -> Result<Vec<u8>, Error> {
+	let mut out = Vec::new();
+	out.extend_from_slice(&(records.len() as u64).to_le_bytes());
+
+	for record in records {
+		write_record(record, &mut out)?;
+	}
+
+	Ok(out)
+}
+
+/// Decode a list of records, which has to span all of `bytes`
+pub(crate) fn decode_all(bytes: &[u8]) -> Result<Vec<FileModify>, Error> {
+	let mut reader = Reader{ bytes, pos: 0 };
+	let count = reader.u64()?;
+
+	// The count is not trusted to reserve memory beyond what the rest of the input can hold
+	let mut records = Vec::with_capacity((count as usize).min(bytes.len() / MIN_RECORD_SIZE));
+	for _ in 0..count {
+		records.push(reader.record()?);
+	}
+
+	reader.finish()?;
+	Ok(records)
+}
+
+
+fn write_record(record: &FileModify, out: &mut Vec<u8>) -> Result<(), Error> {
+	let filename = match record.filename.to_str() {
+		Some(name) => name,
+		None => return Err(Error::InvalidUnicodeData),
+	};
+	let (secs, nanos) = to_unix(record.date)?;
+
+	write_string(filename, out);
+	out.extend_from_slice(&secs.to_le_bytes());
+	out.extend_from_slice(&nanos.to_le_bytes());
+
+	match &record.hash {
+		Some(hash) => {
+			out.push(1);
+			write_string(hash, out);
+		},
+		None => out.push(0),
+	}
+
+	Ok(())
+}
+
+fn write_string(s: &str, out: &mut Vec<u8>) {
+	out.extend_from_slice(&(s.len() as u64).to_le_bytes());
+	out.extend_from_slice(s.as_bytes());
+}
+
+fn invalid(msg: &str) -> Error {
+	Error::InvalidSnapshot(format!("Bincode: {}", msg))
+}
+
+
+struct Reader<'a> {
+	bytes: &'a [u8],
+	pos: usize,
+}
+
+impl<'a> Reader<'a> {
+	fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+		match self.bytes.get(self.pos..).and_then(|rest| rest.get(..len)) {
+			Some(taken) => {
+				self.pos += len;
+				Ok(taken)
+			},
+			None => Err(invalid(&format!("unexpected end of input at byte {}", self.pos))),
+		}
+	}
+
+	fn u64(&mut self) -> Result<u64, Error> {
+		let mut buf = [0; 8];
+		buf.copy_from_slice(self.take(8)?);
+		Ok(u64::from_le_bytes(buf))
+	}
+
+	fn u32(&mut self) -> Result<u32, Error> {
+		let mut buf = [0; 4];
+		buf.copy_from_slice(self.take(4)?);
+		Ok(u32::from_le_bytes(buf))
+	}
+
+	fn string(&mut self) -> Result<&'a str, Error> {
+		let len = self.u64()?;
+		let len = match usize::try_from(len) {
+			Ok(len) => len,
+			Err(_) => return Err(invalid(&format!("string length out of range: {}", len))),
+		};
+
+		match std::str::from_utf8(self.take(len)?) {
+			Ok(s) => Ok(s),
+			Err(_) => Err(Error::InvalidUnicodeData),
+		}
+	}
+
+	fn record(&mut self) -> Result<FileModify, Error> {
+		let filename = PathBuf::from(self.string()?);
+		let secs = self.u64()?;
+		let nanos = self.u32()?;
+
+		let hash = match self.take(1)?[0] {
+			0 => None,
+			1 => Some(self.string()?.to_string()),
+			tag => return Err(invalid(&format!("invalid option tag {} at byte {}", tag, self.pos - 1))),
+		};
+
+		let mut record = FileModify::new(filename, from_unix(secs, nanos)?);
+		record.hash = hash;
+		Ok(record)
+	}
+
+	/// Check that the whole input was read
+	fn finish(&self) -> Result<(), Error> {
+		if self.pos == self.bytes.len() {
+			Ok(())
+		} else {
+			Err(invalid(&format!("trailing bytes at byte {}", self.pos)))
+		}
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::{decode, decode_all, encode, encode_all};
+	use crate::{Error, FileModify};
+
+	use std::time::{Duration, UNIX_EPOCH};
+
+	fn record(hash: Option<&str>) -> FileModify {
+		let mut record = FileModify::new("dé.png".into(), UNIX_EPOCH + Duration::new(0x01_0203_0405, 0x0A0B_0C0D));
+		record.hash = hash.map(String::from);
+		record
+	}
+
+	fn is_invalid(result: Result<FileModify, Error>) -> bool {
+		matches!(result, Err(Error::InvalidSnapshot(_)))
+	}
+
+	#[test]
+	fn records_have_the_layout_of_bincode() {
+		let mut expected = vec![7, 0, 0, 0, 0, 0, 0, 0];
+		expected.extend_from_slice("dé.png".as_bytes());
+		expected.extend_from_slice(&[0x05, 0x04, 0x03, 0x02, 0x01, 0, 0, 0]);
+		expected.extend_from_slice(&[0x0D, 0x0C, 0x0B, 0x0A]);
+
+		let mut without_hash = expected.clone();
+		without_hash.push(0);
+		assert_eq!(encode(&record(None)).unwrap(), without_hash);
+		assert_eq!(decode(&without_hash).unwrap(), record(None));
+
+		let mut with_hash = expected;
+		with_hash.extend_from_slice(&[1, 3, 0, 0, 0, 0, 0, 0, 0, b'a', b'b', b'c']);
+		assert_eq!(encode(&record(Some("abc"))).unwrap(), with_hash);
+		assert_eq!(decode(&with_hash).unwrap(), record(Some("abc")));
+	}
+
+	#[test]
+	fn lists_start_with_their_count() {
+		let records = vec![record(None), record(Some("abc"))];
+		let bytes = encode_all(&records).unwrap();
+
+		assert_eq!(bytes[..8], [2, 0, 0, 0, 0, 0, 0, 0]);
+		assert_eq!(bytes[8..], [encode(&records[0]).unwrap(), encode(&records[1]).unwrap()].concat()[..]);
+		assert_eq!(decode_all(&bytes).unwrap(), records);
+		assert_eq!(decode_all(&[0; 8]).unwrap(), Vec::new());
+	}
+
+	#[test]
+	fn truncated_input_is_rejected() {
+		let bytes = encode(&record(Some("abc"))).unwrap();
+
+		for end in 0..bytes.len() {
+			assert!(is_invalid(decode(&bytes[..end])), "{} bytes", end);
+		}
+
+		// A count larger than the records that follow
+		let mut list = encode_all(&[record(None)]).unwrap();
+		list[0] = 2;
+		assert!(matches!(decode_all(&list), Err(Error::InvalidSnapshot(_))));
+		// A huge count does not reserve memory for it
+		assert!(matches!(decode_all(&[0xFF; 8]), Err(Error::InvalidSnapshot(_))));
+	}
+
+	#[test]
+	fn trailing_bytes_and_bad_option_tags_are_rejected() {
+		let mut bytes = encode(&record(None)).unwrap();
+		bytes.push(0);
+		assert!(is_invalid(decode(&bytes)));
+
+		let mut bytes = encode(&record(None)).unwrap();
+		*bytes.last_mut().unwrap() = 2;
+		assert!(is_invalid(decode(&bytes)));
+	}
+
+	#[test]
+	fn invalid_utf8_and_nanoseconds_are_rejected() {
+		let mut bytes = encode(&record(None)).unwrap();
+		bytes[8] = 0xFF;
+		assert_eq!(decode(&bytes), Err(Error::InvalidUnicodeData));
+
+		let mut bytes = encode(&record(None)).unwrap();
+		let nanos = 8 + 7 + 8;
+		bytes[nanos..nanos + 4].copy_from_slice(&1_000_000_000u32.to_le_bytes());
+		assert!(is_invalid(decode(&bytes)));
+	}
+}
@@ -0,0 +1,423 @@
+//! Minimal JSON reader and writer for `FileModify` records
+//!
+//! A record is stored as
+//! `{"filename":"assets/a.png","date":{"secs_since_epoch":1546300800,"nanos_since_epoch":0}}`,
+//! followed by `"hash":"..."` when the record has a content hash, and a manifest of records as an array of them,
+//! one per line
+
+use crate::{Error, FileModify};
+use super::{from_unix, to_unix};
+
+use std::path::PathBuf;
+
+
+
+/// Encode a record as a JSON object
+pub(crate) fn encode(record: &FileModify) -> Result<String, Error> {
+	let mut out = String::new();
+	write_record(record, &mut out)?;
+	Ok(out)
+}
+
+/// Decode a record from a JSON object
+pub(crate) fn decode(text: &str) -> Result<FileModify, Error> {
+	let mut parser = Parser::new(text);
+	let value = parser.parse_value()?;
+	parser.finish()?;
+
+	record_from_value(&value)
+}
+
+/// Encode records as a JSON array of objects, one per line so manifests diff well
+pub(crate) fn encode_all(records: &[FileModify]) -> Result<String, Error> {
+	let mut out = String::from("[");
+
+	for (i, record) in records.iter().enumerate() {
+		out.push_str(if i == 0 { "\n\t" } else { ",\n\t" });
+		write_record(record, &mut out)?;
+	}
+
+	out.push_str("\n]\n");
+	Ok(out)
+}
+
+/// Decode records from a JSON array of objects
+pub(crate) fn decode_all(text: &str) -> Result<Vec<FileModify>, Error> {
+	let mut parser = Parser::new(text);
+	let value = parser.parse_value()?;
+	parser.finish()?;
+
+	match value {
+		Value::Array(items) => items.iter().map(record_from_value).collect(),
+		_ => Err(invalid("expected an array of records")),
+	}
+}
+
+
+fn write_record(record: &FileModify, out: &mut String) -> Result<(), Error> {
+	let filename = match record.filename.to_str() {
+		Some(name) => name,
+		None => return Err(Error::InvalidUnicodeData),
+	};
+	let (secs, nanos) = to_unix(record.date)?;
+
+	out.push_str("{\"filename\":");
+	write_string(filename, out);
+	out.push_str(&format!(",\"date\":{{\"secs_since_epoch\":{},\"nanos_since_epoch\":{}}}", secs, nanos));
+
+	if let Some(hash) = &record.hash {
+		out.push_str(",\"hash\":");
+		write_string(hash, out);
+	}
+
+	out.push('}');
+	Ok(())
+}
+
+fn write_string(s: &str, out: &mut String) {
+	out.push('"');
+
+	for c in s.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+			c => out.push(c),
+		}
+	}
+
+	out.push('"');
+}
+
+
+fn record_from_value(value: &Value) -> Result<FileModify, Error> {
+	let filename = match value.get("filename") {
+		Some(Value::String(name)) => PathBuf::from(name),
+		_ => return Err(invalid("expected a string field `filename`")),
+	};
+
+	let date = match value.get("date") {
+		Some(date) => date,
+		None => return Err(invalid("expected an object field `date`")),
+	};
+
+	let secs = match date.get("secs_since_epoch") {
+		Some(Value::Number(n)) => *n,
+		_ => return Err(invalid("expected an integer field `secs_since_epoch`")),
+	};
+
+	let nanos = match date.get("nanos_since_epoch") {
+		Some(Value::Number(n)) if *n <= u64::from(u32::MAX) => *n as u32,
+		_ => return Err(invalid("expected an integer field `nanos_since_epoch`")),
+	};
+
+	let hash = match value.get("hash") {
+		Some(Value::String(hash)) => Some(hash.clone()),
+		None => None,
+		Some(_) => return Err(invalid("expected a string field `hash`")),
+	};
+
+	let mut record = FileModify::new(filename, from_unix(secs, nanos)?);
+	record.hash = hash;
+	Ok(record)
+}
+
+fn invalid(msg: &str) -> Error {
+	Error::InvalidSnapshot(format!("JSON: {}", msg))
+}
+
+
+/// The deepest arrays and objects can be nested, which is far more than records need. It keeps malformed
+/// input from overflowing the stack of the recursive parser
+const MAX_DEPTH: usize = 64;
+
+
+/// The subset of JSON values a record can contain
+///
+/// Non negative integers are kept, which is all a record stores. Other numbers, `true`, `false` and `null`
+/// are accepted in unknown fields but their value is not kept
+enum Value {
+	Other,
+	Number(u64),
+	String(String),
+	Array(Vec<Value>),
+	Object(Vec<(String, Value)>),
+}
+
+impl Value {
+	fn get(&self, key: &str) -> Option<&Value> {
+		match self {
+			Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+			_ => None,
+		}
+	}
+}
+
+
+struct Parser<'a> {
+	text: &'a str,
+	pos: usize,
+	/// The number of arrays and objects being parsed
+	depth: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn new(text: &'a str) -> Parser<'a> {
+		Parser{
+			text,
+			pos: 0,
+			depth: 0,
+		}
+	}
+
+	/// Go one array or object deeper
+	fn nest(&mut self) -> Result<(), Error> {
+		self.depth += 1;
+
+		if self.depth > MAX_DEPTH {
+			return Err(invalid(&format!("nested deeper than {} levels at byte {}", MAX_DEPTH, self.pos)));
+		}
+
+		Ok(())
+	}
+
+	fn peek(&self) -> Option<char> {
+		self.text[self.pos..].chars().next()
+	}
+
+	fn bump(&mut self) -> Option<char> {
+		let c = self.peek()?;
+		self.pos += c.len_utf8();
+		Some(c)
+	}
+
+	fn skip_whitespace(&mut self) {
+		while let Some(c) = self.peek() {
+			if !c.is_ascii_whitespace() {
+				break;
+			}
+			self.bump();
+		}
+	}
+
+	fn expect(&mut self, expected: char) -> Result<(), Error> {
+		self.skip_whitespace();
+
+		match self.bump() {
+			Some(c) if c == expected => Ok(()),
+			_ => Err(invalid(&format!("expected `{}` at byte {}", expected, self.pos))),
+		}
+	}
+
+	/// Check that nothing but whitespace follows the parsed value
+	fn finish(&mut self) -> Result<(), Error> {
+		self.skip_whitespace();
+
+		match self.peek() {
+			None => Ok(()),
+			Some(_) => Err(invalid(&format!("trailing characters at byte {}", self.pos))),
+		}
+	}
+
+	fn parse_value(&mut self) -> Result<Value, Error> {
+		self.skip_whitespace();
+
+		match self.peek() {
+			Some('{') => {
+				self.nest()?;
+				let object = self.parse_object();
+				self.depth -= 1;
+				object
+			},
+			Some('[') => {
+				self.nest()?;
+				let array = self.parse_array();
+				self.depth -= 1;
+				array
+			},
+			Some('"') => Ok(Value::String(self.parse_string()?)),
+			Some(c) if c.is_ascii_digit() || c == '-' => self.parse_number(),
+			Some(_) => self.parse_literal(),
+			None => Err(invalid("unexpected end of input")),
+		}
+	}
+
+	fn parse_object(&mut self) -> Result<Value, Error> {
+		let mut fields = Vec::new();
+		self.expect('{')?;
+		self.skip_whitespace();
+
+		if self.peek() == Some('}') {
+			self.bump();
+			return Ok(Value::Object(fields));
+		}
+
+		loop {
+			self.skip_whitespace();
+			let key = self.parse_string()?;
+			self.expect(':')?;
+			fields.push((key, self.parse_value()?));
+
+			self.skip_whitespace();
+			match self.bump() {
+				Some(',') => continue,
+				Some('}') => return Ok(Value::Object(fields)),
+				_ => return Err(invalid(&format!("expected `,` or `}}` at byte {}", self.pos))),
+			}
+		}
+	}
+
+	fn parse_array(&mut self) -> Result<Value, Error> {
+		let mut items = Vec::new();
+		self.expect('[')?;
+		self.skip_whitespace();
+
+		if self.peek() == Some(']') {
+			self.bump();
+			return Ok(Value::Array(items));
+		}
+
+		loop {
+			items.push(self.parse_value()?);
+
+			self.skip_whitespace();
+			match self.bump() {
+				Some(',') => continue,
+				Some(']') => return Ok(Value::Array(items)),
+				_ => return Err(invalid(&format!("expected `,` or `]` at byte {}", self.pos))),
+			}
+		}
+	}
+
+	fn parse_string(&mut self) -> Result<String, Error> {
+		let mut out = String::new();
+
+		if self.bump() != Some('"') {
+			return Err(invalid(&format!("expected a string at byte {}", self.pos)));
+		}
+
+		loop {
+			match self.bump() {
+				Some('"') => return Ok(out),
+				Some('\\') => match self.bump() {
+					Some('"') => out.push('"'),
+					Some('\\') => out.push('\\'),
+					Some('/') => out.push('/'),
+					Some('b') => out.push('\u{8}'),
+					Some('f') => out.push('\u{c}'),
+					Some('n') => out.push('\n'),
+					Some('r') => out.push('\r'),
+					Some('t') => out.push('\t'),
+					Some('u') => out.push(self.parse_unicode_escape()?),
+					_ => return Err(invalid(&format!("invalid escape at byte {}", self.pos))),
+				},
+				Some(c) => out.push(c),
+				None => return Err(invalid("unterminated string")),
+			}
+		}
+	}
+
+	/// Parse the `XXXX` of a `\uXXXX` escape, joining surrogate pairs
+	fn parse_unicode_escape(&mut self) -> Result<char, Error> {
+		let high = self.parse_hex4()?;
+
+		let code = if (0xD800..0xDC00).contains(&high) {
+			if self.bump() != Some('\\') || self.bump() != Some('u') {
+				return Err(invalid("unpaired surrogate in string"));
+			}
+
+			let low = self.parse_hex4()?;
+			if !(0xDC00..0xE000).contains(&low) {
+				return Err(invalid("unpaired surrogate in string"));
+			}
+
+			0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+		} else {
+			high
+		};
+
+		match std::char::from_u32(code) {
+			Some(c) => Ok(c),
+			None => Err(invalid("invalid unicode escape")),
+		}
+	}
+
+	fn parse_hex4(&mut self) -> Result<u32, Error> {
+		let start = self.pos;
+
+		for _ in 0..4 {
+			match self.bump() {
+				Some(c) if c.is_ascii_hexdigit() => (),
+				_ => return Err(invalid(&format!("invalid unicode escape at byte {}", start))),
+			}
+		}
+
+		Ok(u32::from_str_radix(&self.text[start..self.pos], 16).unwrap())
+	}
+
+	/// Parse a number, kept when it is a non negative integer fitting in a `u64`
+	fn parse_number(&mut self) -> Result<Value, Error> {
+		let start = self.pos;
+
+		if self.peek() == Some('-') {
+			self.bump();
+		}
+
+		// No leading zeros
+		match self.peek() {
+			Some('0') => { self.bump(); },
+			Some(c) if c.is_ascii_digit() => self.skip_digits(),
+			_ => return Err(invalid(&format!("invalid number at byte {}", start))),
+		}
+		let integer = self.pos;
+
+		if self.peek() == Some('.') {
+			self.bump();
+			if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+				return Err(invalid(&format!("invalid number at byte {}", start)));
+			}
+			self.skip_digits();
+		}
+
+		if let Some('e') | Some('E') = self.peek() {
+			self.bump();
+			if let Some('+') | Some('-') = self.peek() {
+				self.bump();
+			}
+			if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+				return Err(invalid(&format!("invalid number at byte {}", start)));
+			}
+			self.skip_digits();
+		}
+
+		if integer != self.pos {
+			return Ok(Value::Other);
+		}
+
+		Ok(self.text[start..self.pos].parse().map_or(Value::Other, Value::Number))
+	}
+
+	fn skip_digits(&mut self) {
+		while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+			self.bump();
+		}
+	}
+
+	fn parse_literal(&mut self) -> Result<Value, Error> {
+		let rest = &self.text[self.pos..];
+
+		let len = if rest.starts_with("null") || rest.starts_with("true") {
+			4
+		} else if rest.starts_with("false") {
+			5
+		} else {
+			return Err(invalid(&format!("unexpected character at byte {}", self.pos)));
+		};
+
+		self.pos += len;
+		Ok(Value::Other)
+	}
+}
@@ -0,0 +1,388 @@
+//! Minimal RON reader and writer for `FileModify` records
+//!
+//! A record is stored as
+//! `(filename:"assets/a.png",date:(secs_since_epoch:1546300800,nanos_since_epoch:0))`,
+//! like the `ron` crate writes it, with a last `hash:"..."` field when the record has a content hash, and a list of records as a sequence of them, one per line.
+//! Struct names (`FileModify(...)`), trailing commas and comments are accepted when reading
+
+use crate::{Error, FileModify};
+use super::{from_unix, to_unix};
+
+use std::path::PathBuf;
+
+
+
+/// Encode a record as a RON struct
+pub(crate) fn encode(record: &FileModify) -> Result<String, Error> {
+	let mut out = String::new();
+	write_record(record, &mut out)?;
+	Ok(out)
+}
+
+/// Decode a record from a RON struct
+pub(crate) fn decode(text: &str) -> Result<FileModify, Error> {
+	let mut parser = Parser::new(text);
+	let value = parser.parse_value()?;
+	parser.finish()?;
+
+	record_from_value(&value)
+}
+
+/// Encode records as a RON sequence of structs, one per line
+pub(crate) fn encode_all(records: &[FileModify]) -> Result<String, Error> {
+	let mut out = String::from("[");
+
+	for record in records {
+		out.push_str("\n\t");
+		write_record(record, &mut out)?;
+		out.push(',');
+	}
+
+	out.push_str("\n]\n");
+	Ok(out)
+}
+
+/// Decode records from a RON sequence of structs
+pub(crate) fn decode_all(text: &str) -> Result<Vec<FileModify>, Error> {
+	let mut parser = Parser::new(text);
+	let value = parser.parse_value()?;
+	parser.finish()?;
+
+	match value {
+		Value::Seq(items) => items.iter().map(record_from_value).collect(),
+		_ => Err(invalid("expected a sequence of records")),
+	}
+}
+
+
+fn write_record(record: &FileModify, out: &mut String) -> Result<(), Error> {
+	let filename = match record.filename.to_str() {
+		Some(name) => name,
+		None => return Err(Error::InvalidUnicodeData),
+	};
+	let (secs, nanos) = to_unix(record.date)?;
+
+	out.push_str("(filename:");
+	write_string(filename, out);
+	out.push_str(&format!(",date:(secs_since_epoch:{},nanos_since_epoch:{})", secs, nanos));
+
+	if let Some(hash) = &record.hash {
+		out.push_str(",hash:");
+		write_string(hash, out);
+	}
+
+	out.push(')');
+	Ok(())
+}
+
+fn write_string(s: &str, out: &mut String) {
+	out.push('"');
+
+	for c in s.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+			c => out.push(c),
+		}
+	}
+
+	out.push('"');
+}
+
+
+fn record_from_value(value: &Value) -> Result<FileModify, Error> {
+	let filename = match value.get("filename") {
+		Some(Value::String(name)) => PathBuf::from(name),
+		_ => return Err(invalid("expected a string field `filename`")),
+	};
+
+	let date = match value.get("date") {
+		Some(date) => date,
+		None => return Err(invalid("expected a struct field `date`")),
+	};
+
+	let secs = match date.get("secs_since_epoch") {
+		Some(Value::Number(n)) => *n,
+		_ => return Err(invalid("expected an integer field `secs_since_epoch`")),
+	};
+
+	let nanos = match date.get("nanos_since_epoch") {
+		Some(Value::Number(n)) if *n <= u64::from(u32::MAX) => *n as u32,
+		_ => return Err(invalid("expected an integer field `nanos_since_epoch`")),
+	};
+
+	let hash = match value.get("hash") {
+		Some(Value::String(hash)) => Some(hash.clone()),
+		None => None,
+		Some(_) => return Err(invalid("expected a string field `hash`")),
+	};
+
+	let mut record = FileModify::new(filename, from_unix(secs, nanos)?);
+	record.hash = hash;
+	Ok(record)
+}
+
+fn invalid(msg: &str) -> Error {
+	Error::InvalidSnapshot(format!("RON: {}", msg))
+}
+
+
+/// The deepest sequences and structs can be nested, which is far more than records need. It keeps malformed
+/// input from overflowing the stack of the recursive parser
+const MAX_DEPTH: usize = 64;
+
+
+/// The subset of RON values a record can contain
+///
+/// Numbers are restricted to non negative integers, which is all a record stores
+enum Value {
+	Number(u64),
+	String(String),
+	Seq(Vec<Value>),
+	Struct(Vec<(String, Value)>),
+}
+
+impl Value {
+	fn get(&self, key: &str) -> Option<&Value> {
+		match self {
+			Value::Struct(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+			_ => None,
+		}
+	}
+}
+
+
+struct Parser<'a> {
+	text: &'a str,
+	pos: usize,
+	/// The number of sequences and structs being parsed
+	depth: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn new(text: &'a str) -> Parser<'a> {
+		Parser{
+			text,
+			pos: 0,
+			depth: 0,
+		}
+	}
+
+	/// Parse a sequence or a struct with `parse`, one level deeper
+	fn nested(&mut self, parse: fn(&mut Self) -> Result<Value, Error>) -> Result<Value, Error> {
+		if self.depth == MAX_DEPTH {
+			return Err(invalid(&format!("nested deeper than {} levels at byte {}", MAX_DEPTH, self.pos)));
+		}
+
+		self.depth += 1;
+		let value = parse(self);
+		self.depth -= 1;
+
+		value
+	}
+
+	fn peek(&self) -> Option<char> {
+		self.text[self.pos..].chars().next()
+	}
+
+	fn bump(&mut self) -> Option<char> {
+		let c = self.peek()?;
+		self.pos += c.len_utf8();
+		Some(c)
+	}
+
+	/// Skip whitespace, `// line` and `/* block */` comments
+	fn skip_whitespace(&mut self) -> Result<(), Error> {
+		loop {
+			let rest = &self.text[self.pos..];
+
+			if rest.starts_with("//") {
+				self.pos += rest.find('\n').unwrap_or(rest.len());
+			} else if let Some(comment) = rest.strip_prefix("/*") {
+				match comment.find("*/") {
+					Some(end) => self.pos += end + 4,
+					None => return Err(invalid("unterminated comment")),
+				}
+			} else if rest.starts_with(|c: char| c.is_whitespace()) {
+				self.bump();
+			} else {
+				return Ok(());
+			}
+		}
+	}
+
+	fn expect(&mut self, expected: char) -> Result<(), Error> {
+		self.skip_whitespace()?;
+
+		match self.bump() {
+			Some(c) if c == expected => Ok(()),
+			_ => Err(invalid(&format!("expected `{}` at byte {}", expected, self.pos))),
+		}
+	}
+
+	/// Check that nothing but whitespace follows the parsed value
+	fn finish(&mut self) -> Result<(), Error> {
+		self.skip_whitespace()?;
+
+		match self.peek() {
+			None => Ok(()),
+			Some(_) => Err(invalid(&format!("trailing characters at byte {}", self.pos))),
+		}
+	}
+
+	fn parse_value(&mut self) -> Result<Value, Error> {
+		self.skip_whitespace()?;
+
+		match self.peek() {
+			Some('(') => self.nested(Self::parse_struct),
+			Some('[') => self.nested(Self::parse_seq),
+			Some('"') => Ok(Value::String(self.parse_string()?)),
+			Some(c) if c.is_ascii_digit() => self.parse_number(),
+			// The name of a named struct, like `FileModify(...)`
+			Some(c) if c.is_alphabetic() || c == '_' => {
+				self.parse_identifier();
+				self.skip_whitespace()?;
+
+				match self.peek() {
+					Some('(') => self.nested(Self::parse_struct),
+					_ => Err(invalid(&format!("expected `(` after a struct name at byte {}", self.pos))),
+				}
+			},
+			Some(_) => Err(invalid(&format!("unexpected character at byte {}", self.pos))),
+			None => Err(invalid("unexpected end of input")),
+		}
+	}
+
+	fn parse_identifier(&mut self) -> &'a str {
+		let start = self.pos;
+
+		while let Some(c) = self.peek() {
+			if !(c.is_alphanumeric() || c == '_') {
+				break;
+			}
+			self.bump();
+		}
+
+		&self.text[start..self.pos]
+	}
+
+	fn parse_struct(&mut self) -> Result<Value, Error> {
+		let mut fields = Vec::new();
+		self.expect('(')?;
+
+		loop {
+			self.skip_whitespace()?;
+			if self.peek() == Some(')') {
+				self.bump();
+				return Ok(Value::Struct(fields));
+			}
+
+			let key = self.parse_identifier();
+			if key.is_empty() {
+				return Err(invalid(&format!("expected a field name at byte {}", self.pos)));
+			}
+
+			self.expect(':')?;
+			fields.push((key.to_string(), self.parse_value()?));
+
+			self.skip_whitespace()?;
+			match self.bump() {
+				Some(',') => continue,
+				Some(')') => return Ok(Value::Struct(fields)),
+				_ => return Err(invalid(&format!("expected `,` or `)` at byte {}", self.pos))),
+			}
+		}
+	}
+
+	fn parse_seq(&mut self) -> Result<Value, Error> {
+		let mut items = Vec::new();
+		self.expect('[')?;
+
+		loop {
+			self.skip_whitespace()?;
+			if self.peek() == Some(']') {
+				self.bump();
+				return Ok(Value::Seq(items));
+			}
+
+			items.push(self.parse_value()?);
+
+			self.skip_whitespace()?;
+			match self.bump() {
+				Some(',') => continue,
+				Some(']') => return Ok(Value::Seq(items)),
+				_ => return Err(invalid(&format!("expected `,` or `]` at byte {}", self.pos))),
+			}
+		}
+	}
+
+	fn parse_string(&mut self) -> Result<String, Error> {
+		let mut out = String::new();
+		self.bump();
+
+		loop {
+			match self.bump() {
+				Some('"') => return Ok(out),
+				Some('\\') => match self.bump() {
+					Some('"') => out.push('"'),
+					Some('\'') => out.push('\''),
+					Some('\\') => out.push('\\'),
+					Some('/') => out.push('/'),
+					Some('b') => out.push('\u{8}'),
+					Some('f') => out.push('\u{c}'),
+					Some('n') => out.push('\n'),
+					Some('r') => out.push('\r'),
+					Some('t') => out.push('\t'),
+					Some('0') => out.push('\0'),
+					Some('u') => out.push(self.parse_unicode_escape()?),
+					_ => return Err(invalid(&format!("invalid escape at byte {}", self.pos))),
+				},
+				Some(c) => out.push(c),
+				None => return Err(invalid("unterminated string")),
+			}
+		}
+	}
+
+	/// Parse the `XXXX` of a `\uXXXX` escape, or the `{X...}` of a Rust style `\u{X...}` one
+	fn parse_unicode_escape(&mut self) -> Result<char, Error> {
+		let start = self.pos;
+		let rest = &self.text[start..];
+
+		let escape = match rest.strip_prefix('{') {
+			Some(braced) => braced.find('}').map(|end| (&braced[..end], end + 2)),
+			None => rest.get(..4).map(|hex| (hex, 4)),
+		};
+
+		let code = escape
+			.filter(|(hex, _)| !hex.is_empty() && hex.len() <= 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+			.and_then(|(hex, len)| Some((std::char::from_u32(u32::from_str_radix(hex, 16).ok()?)?, len)));
+
+		match code {
+			Some((c, len)) => {
+				self.pos += len;
+				Ok(c)
+			},
+			None => Err(invalid(&format!("invalid unicode escape at byte {}", start))),
+		}
+	}
+
+	fn parse_number(&mut self) -> Result<Value, Error> {
+		let start = self.pos;
+
+		while let Some(c) = self.peek() {
+			if !c.is_ascii_digit() {
+				break;
+			}
+			self.bump();
+		}
+
+		match self.text[start..self.pos].parse() {
+			Ok(n) => Ok(Value::Number(n)),
+			Err(_) => Err(invalid(&format!("integer out of range at byte {}", start))),
+		}
+	}
+}
@@ -0,0 +1,550 @@
+//! Minimal TOML reader and writer for `FileModify` records
+//!
+//! A record is stored as
+//! ```toml
+//! filename = "assets/a.png"
+//! date = 2019-01-01T00:00:00.5Z
+//! ```
+//! with its date as a UTC offset date-time, followed by `hash = "..."` when the record has a content hash,
+//! and a list of records as an array of `[[record]]` tables.
+//! Only the subset of TOML records need is read: bare keys, basic and literal strings, and offset date-times
+
+use crate::{Error, FileModify};
+use super::{from_unix, to_unix};
+
+use std::path::PathBuf;
+
+
+
+/// The last date a TOML date-time can hold, 9999-12-31T23:59:59Z
+const MAX_SECS: u64 = 253_402_300_799;
+
+const SECS_PER_DAY: i64 = 86_400;
+
+
+/// Encode a record as the key/value pairs of a TOML document
+pub(crate) fn encode(record: &FileModify) -> Result<String, Error> {
+	let mut out = String::new();
+	write_record(record, &mut out)?;
+	Ok(out)
+}
+
+/// Decode a record from the key/value pairs of a TOML document
+pub(crate) fn decode(text: &str) -> Result<FileModify, Error> {
+	let document = parse(text)?;
+
+	if !document.records.is_empty() {
+		return Err(invalid("expected a single record, found `[[record]]` tables"));
+	}
+
+	record_from_table(&document.root)
+}
+
+/// Encode records as an array of `[[record]]` tables, separated by blank lines
+pub(crate) fn encode_all(records: &[FileModify]) -> Result<String, Error> {
+	let mut out = String::new();
+
+	for (i, record) in records.iter().enumerate() {
+		out.push_str(if i == 0 { "[[record]]\n" } else { "\n[[record]]\n" });
+		write_record(record, &mut out)?;
+	}
+
+	Ok(out)
+}
+
+/// Decode records from an array of `[[record]]` tables
+pub(crate) fn decode_all(text: &str) -> Result<Vec<FileModify>, Error> {
+	let document = parse(text)?;
+
+	if !document.root.is_empty() {
+		return Err(invalid("expected `[[record]]` tables, found keys outside of them"));
+	}
+
+	document.records.iter().map(|table| record_from_table(table)).collect()
+}
+
+
+fn write_record(record: &FileModify, out: &mut String) -> Result<(), Error> {
+	let filename = match record.filename.to_str() {
+		Some(name) => name,
+		None => return Err(Error::InvalidUnicodeData),
+	};
+	let (secs, nanos) = to_unix(record.date)?;
+
+	out.push_str("filename = ");
+	write_string(filename, out);
+	out.push_str("\ndate = ");
+	write_date(secs, nanos, out)?;
+	out.push('\n');
+
+	if let Some(hash) = &record.hash {
+		out.push_str("hash = ");
+		write_string(hash, out);
+		out.push('\n');
+	}
+
+	Ok(())
+}
+
+fn write_string(s: &str, out: &mut String) {
+	out.push('"');
+
+	for c in s.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c if (c as u32) < 0x20 || c == '\u{7f}' => out.push_str(&format!("\\u{:04X}", c as u32)),
+			c => out.push(c),
+		}
+	}
+
+	out.push('"');
+}
+
+/// Write a date as a UTC date-time, with as many fractional digits as its nanoseconds need
+fn write_date(secs: u64, nanos: u32, out: &mut String) -> Result<(), Error> {
+	if secs > MAX_SECS {
+		return Err(invalid("date is later than the year 9999"));
+	}
+
+	let secs = secs as i64;
+	let (year, month, day) = civil_from_days(secs / SECS_PER_DAY);
+	let time = secs % SECS_PER_DAY;
+
+	out.push_str(&format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}", year, month, day, time / 3600, time / 60 % 60, time % 60));
+
+	if nanos > 0 {
+		out.push_str(format!(".{:09}", nanos).trim_end_matches('0'));
+	}
+
+	out.push('Z');
+	Ok(())
+}
+
+
+fn record_from_table(table: &[(String, Value)]) -> Result<FileModify, Error> {
+	let get = |key: &str| table.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+
+	let filename = match get("filename") {
+		Some(Value::String(name)) => PathBuf::from(name),
+		_ => return Err(invalid("expected a string key `filename`")),
+	};
+
+	let (secs, nanos) = match get("date") {
+		Some(Value::Date(secs, nanos)) => (*secs, *nanos),
+		_ => return Err(invalid("expected a date-time key `date`")),
+	};
+
+	let hash = match get("hash") {
+		Some(Value::String(hash)) => Some(hash.clone()),
+		None => None,
+		Some(_) => return Err(invalid("expected a string key `hash`")),
+	};
+
+	let mut record = FileModify::new(filename, from_unix(secs, nanos)?);
+	record.hash = hash;
+	Ok(record)
+}
+
+fn invalid(msg: &str) -> Error {
+	Error::InvalidSnapshot(format!("TOML: {}", msg))
+}
+
+
+/// Days since the Unix epoch of a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+	let year = if month <= 2 { year - 1 } else { year };
+	let era = year.div_euclid(400);
+	let year_of_era = year.rem_euclid(400);
+	let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+	let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+	era * 146_097 + day_of_era - 719_468
+}
+
+/// The date in the proleptic Gregorian calendar of a number of days since the Unix epoch
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+	let days = days + 719_468;
+	let era = days.div_euclid(146_097);
+	let day_of_era = days.rem_euclid(146_097);
+	let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let shifted_month = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+	let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+
+	(year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+	match month {
+		2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+		2 => 28,
+		4 | 6 | 9 | 11 => 30,
+		_ => 31,
+	}
+}
+
+
+/// The values a record can contain
+enum Value {
+	String(String),
+	/// Seconds and nanoseconds since the Unix epoch
+	Date(u64, u32),
+}
+
+/// The key/value pairs before the first table, and those of every `[[record]]` table
+struct Document {
+	root: Vec<(String, Value)>,
+	records: Vec<Vec<(String, Value)>>,
+}
+
+fn parse(text: &str) -> Result<Document, Error> {
+	let mut document = Document{ root: Vec::new(), records: Vec::new() };
+
+	for (number, line) in text.lines().enumerate() {
+		let mut parser = Parser{ line, pos: 0, number: number + 1 };
+		parser.skip_whitespace();
+
+		match parser.peek() {
+			None | Some('#') => continue,
+			Some('[') => {
+				parser.table_header()?;
+				document.records.push(Vec::new());
+			},
+			Some(_) => {
+				let (key, value) = parser.key_value()?;
+				let table = document.records.last_mut().unwrap_or(&mut document.root);
+
+				if table.iter().any(|(k, _)| *k == key) {
+					return Err(parser.error(&format!("duplicate key `{}`", key)));
+				}
+				table.push((key, value));
+			},
+		}
+
+		parser.finish()?;
+	}
+
+	Ok(document)
+}
+
+
+/// A reader over a single line of the document
+struct Parser<'a> {
+	line: &'a str,
+	pos: usize,
+	number: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn error(&self, msg: &str) -> Error {
+		invalid(&format!("{} on line {}", msg, self.number))
+	}
+
+	fn peek(&self) -> Option<char> {
+		self.line[self.pos..].chars().next()
+	}
+
+	fn bump(&mut self) -> Option<char> {
+		let c = self.peek()?;
+		self.pos += c.len_utf8();
+		Some(c)
+	}
+
+	fn eat(&mut self, expected: &str) -> bool {
+		let found = self.line[self.pos..].starts_with(expected);
+		if found {
+			self.pos += expected.len();
+		}
+		found
+	}
+
+	fn skip_whitespace(&mut self) {
+		while let Some(' ' | '\t') = self.peek() {
+			self.bump();
+		}
+	}
+
+	/// Check that nothing but whitespace or a comment follows what was parsed
+	fn finish(&mut self) -> Result<(), Error> {
+		self.skip_whitespace();
+
+		match self.peek() {
+			None | Some('#') => Ok(()),
+			Some(_) => Err(self.error("unexpected characters after the value")),
+		}
+	}
+
+	fn table_header(&mut self) -> Result<(), Error> {
+		if !self.eat("[[") {
+			return Err(self.error("only `[[record]]` tables are supported"));
+		}
+
+		self.skip_whitespace();
+		let name = self.bare_key();
+		self.skip_whitespace();
+
+		if name != "record" || !self.eat("]]") {
+			return Err(self.error("only `[[record]]` tables are supported"));
+		}
+
+		Ok(())
+	}
+
+	fn bare_key(&mut self) -> &'a str {
+		let start = self.pos;
+
+		while let Some(c) = self.peek() {
+			if !(c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+				break;
+			}
+			self.bump();
+		}
+
+		&self.line[start..self.pos]
+	}
+
+	fn key_value(&mut self) -> Result<(String, Value), Error> {
+		let key = self.bare_key();
+		if key.is_empty() {
+			return Err(self.error("expected a bare key"));
+		}
+
+		self.skip_whitespace();
+		if !self.eat("=") {
+			return Err(self.error("expected `=` after the key"));
+		}
+		self.skip_whitespace();
+
+		let value = match self.peek() {
+			Some('"') => Value::String(self.basic_string()?),
+			Some('\'') => Value::String(self.literal_string()?),
+			Some(c) if c.is_ascii_digit() => self.date()?,
+			_ => return Err(self.error("expected a string or a date-time")),
+		};
+
+		Ok((key.to_string(), value))
+	}
+
+	fn basic_string(&mut self) -> Result<String, Error> {
+		let mut out = String::new();
+		self.bump();
+
+		loop {
+			match self.bump() {
+				Some('"') => return Ok(out),
+				Some('\\') => match self.bump() {
+					Some('"') => out.push('"'),
+					Some('\\') => out.push('\\'),
+					Some('b') => out.push('\u{8}'),
+					Some('f') => out.push('\u{c}'),
+					Some('n') => out.push('\n'),
+					Some('r') => out.push('\r'),
+					Some('t') => out.push('\t'),
+					Some('u') => out.push(self.unicode_escape(4)?),
+					Some('U') => out.push(self.unicode_escape(8)?),
+					_ => return Err(self.error("invalid escape in string")),
+				},
+				Some(c) => out.push(c),
+				None => return Err(self.error("unterminated string")),
+			}
+		}
+	}
+
+	fn literal_string(&mut self) -> Result<String, Error> {
+		self.bump();
+		let start = self.pos;
+
+		match self.line[start..].find('\'') {
+			Some(len) => {
+				self.pos += len + 1;
+				Ok(self.line[start..start + len].to_string())
+			},
+			None => Err(self.error("unterminated string")),
+		}
+	}
+
+	fn unicode_escape(&mut self, digits: usize) -> Result<char, Error> {
+		let hex = self.line.get(self.pos..self.pos + digits).filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()));
+
+		match hex.and_then(|hex| u32::from_str_radix(hex, 16).ok()).and_then(std::char::from_u32) {
+			Some(c) => {
+				self.pos += digits;
+				Ok(c)
+			},
+			None => Err(self.error("invalid unicode escape in string")),
+		}
+	}
+
+	/// A number of exactly `digits` decimal digits
+	fn number(&mut self, digits: usize) -> Result<i64, Error> {
+		let number = self.line.get(self.pos..self.pos + digits).filter(|n| n.bytes().all(|b| b.is_ascii_digit()));
+
+		match number {
+			Some(number) => {
+				self.pos += digits;
+				Ok(number.parse().unwrap())
+			},
+			None => Err(self.error("invalid date-time")),
+		}
+	}
+
+	fn separator(&mut self, expected: char) -> Result<(), Error> {
+		match self.bump() {
+			Some(c) if c == expected => Ok(()),
+			_ => Err(self.error("invalid date-time")),
+		}
+	}
+
+	/// An offset date-time (`1979-05-27T07:32:00.999999Z`, `1979-05-27 00:32:00-07:00`)
+	///
+	/// Fractional digits past nanoseconds are truncated
+	fn date(&mut self) -> Result<Value, Error> {
+		let year = self.number(4)?;
+		self.separator('-')?;
+		let month = self.number(2)?;
+		self.separator('-')?;
+		let day = self.number(2)?;
+
+		match self.bump() {
+			Some('T' | 't' | ' ') => (),
+			_ => return Err(self.error("expected a date-time with a time and an offset")),
+		}
+
+		let hour = self.number(2)?;
+		self.separator(':')?;
+		let minute = self.number(2)?;
+		self.separator(':')?;
+		let second = self.number(2)?;
+
+		let mut nanos = 0;
+		if self.eat(".") {
+			let start = self.pos;
+			while let Some(c) = self.peek().filter(char::is_ascii_digit) {
+				if self.pos - start < 9 {
+					nanos = nanos * 10 + c.to_digit(10).unwrap();
+				}
+				self.bump();
+			}
+
+			match self.pos - start {
+				0 => return Err(self.error("invalid date-time")),
+				digits => nanos *= 10u32.pow(9u32.saturating_sub(digits as u32)),
+			}
+		}
+
+		let offset = match self.bump() {
+			Some('Z' | 'z') => 0,
+			Some(sign @ ('+' | '-')) => {
+				let hours = self.number(2)?;
+				self.separator(':')?;
+				let minutes = self.number(2)?;
+
+				if hours > 23 || minutes > 59 {
+					return Err(self.error("invalid date-time offset"));
+				}
+
+				let offset = hours * 3600 + minutes * 60;
+				if sign == '-' { -offset } else { offset }
+			},
+			_ => return Err(self.error("expected a date-time with an offset")),
+		};
+
+		if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) || hour > 23 || minute > 59 || second > 59 {
+			return Err(self.error("invalid date-time"));
+		}
+
+		let secs = days_from_civil(year, month, day) * SECS_PER_DAY + hour * 3600 + minute * 60 + second - offset;
+		if secs < 0 {
+			return Err(self.error("date is earlier than the Unix epoch"));
+		}
+
+		Ok(Value::Date(secs as u64, nanos))
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::{civil_from_days, days_from_civil, decode, encode, MAX_SECS, SECS_PER_DAY};
+	use crate::{Error, FileModify};
+
+	use std::time::{Duration, UNIX_EPOCH};
+
+	fn record(secs: u64, nanos: u32) -> FileModify {
+		FileModify::new("a".into(), UNIX_EPOCH + Duration::new(secs, nanos))
+	}
+
+	/// Encode a record dated `secs` and `nanos`, check its date-time and decode it back
+	fn round_trip(secs: u64, nanos: u32, date: &str) {
+		let encoded = encode(&record(secs, nanos)).unwrap();
+		assert!(encoded.contains(&format!("date = {}\n", date)), "{}", encoded);
+		assert_eq!(decode(&encoded).unwrap(), record(secs, nanos));
+	}
+
+	fn date(text: &str) -> Result<FileModify, Error> {
+		decode(&format!("filename = \"a\"\ndate = {}\n", text))
+	}
+
+	#[test]
+	fn dates_round_trip() {
+		round_trip(0, 0, "1970-01-01T00:00:00Z");
+		round_trip(1_546_300_800, 500_000_000, "2019-01-01T00:00:00.5Z");
+		round_trip(1_700_000_000, 1, "2023-11-14T22:13:20.000000001Z");
+		round_trip(951_782_400, 123_456_789, "2000-02-29T00:00:00.123456789Z");
+		round_trip(MAX_SECS, 999_999_999, "9999-12-31T23:59:59.999999999Z");
+	}
+
+	#[test]
+	fn dates_past_the_year_9999_are_rejected() {
+		assert!(matches!(encode(&record(MAX_SECS + 1, 0)), Err(Error::InvalidSnapshot(_))));
+		assert_eq!(days_from_civil(9999, 12, 31) * SECS_PER_DAY + SECS_PER_DAY - 1, MAX_SECS as i64);
+	}
+
+	#[test]
+	fn offsets_and_extra_digits_are_read() {
+		assert_eq!(date("1970-01-01T01:00:00+01:00").unwrap(), record(0, 0));
+		assert_eq!(date("1969-12-31 23:30:00-00:30").unwrap(), record(0, 0));
+		assert_eq!(date("1970-01-01t00:00:01.1234567891z").unwrap(), record(1, 123_456_789));
+	}
+
+	#[test]
+	fn dates_before_the_epoch_are_rejected() {
+		assert!(matches!(date("1969-12-31T23:59:59Z"), Err(Error::InvalidSnapshot(_))));
+		assert!(matches!(date("1970-01-01T00:00:00+00:01"), Err(Error::InvalidSnapshot(_))));
+		assert!(matches!(encode(&FileModify::new("a".into(), UNIX_EPOCH - Duration::new(1, 0))), Err(Error::InvalidSnapshot(_))));
+	}
+
+	#[test]
+	fn invalid_dates_are_rejected() {
+		for text in ["2019-02-29T00:00:00Z", "2019-13-01T00:00:00Z", "2019-01-01T24:00:00Z", "2019-01-01T00:00:00", "2019-01-01", "2019-01-01T00:00:00.Z"] {
+			assert!(matches!(date(text), Err(Error::InvalidSnapshot(_))), "{}", text);
+		}
+	}
+
+	#[test]
+	fn calendar_conversions_cover_dates_before_1970() {
+		for (days, civil) in [
+			(0, (1970, 1, 1)),
+			(-1, (1969, 12, 31)),
+			(-25_508, (1900, 3, 1)),
+			(-25_509, (1900, 2, 28)),
+			(-135_081, (1600, 2, 29)),
+			(-719_468, (0, 3, 1)),
+			(11_016, (2000, 2, 29)),
+		] {
+			assert_eq!(civil_from_days(days), civil, "{} days", days);
+			assert_eq!(days_from_civil(civil.0, civil.1, civil.2), days, "{:?}", civil);
+		}
+
+		// Every day of four centuries, across the epoch, leap years included
+		for days in -146_097..146_097 {
+			let (year, month, day) = civil_from_days(days);
+			assert_eq!(days_from_civil(year, month, day), days);
+		}
+	}
+}
@@ -0,0 +1,120 @@
+#![cfg(feature = "async")]
+
+use file_processor::{CancellationToken, Error, FindOptions};
+
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake};
+use std::thread::Thread;
+
+
+
+/// Wakes the thread blocked in `block_on`
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+	fn wake(self: Arc<Self>) {
+		self.0.unpark();
+	}
+}
+
+/// A minimal executor, polling `future` on the current thread until it completes
+fn block_on<F: Future>(future: F) -> F::Output {
+	let mut future = Box::pin(future);
+	let waker = Arc::new(Unpark(std::thread::current())).into();
+	let mut cx = Context::from_waker(&waker);
+
+	loop {
+		match future.as_mut().poll(&mut cx) {
+			Poll::Ready(output) => return output,
+			Poll::Pending => std::thread::park(),
+		}
+	}
+}
+
+/// A future pending once before it completes, like one waiting on the network
+struct YieldOnce(bool);
+
+impl Future for YieldOnce {
+	type Output = ();
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+		if self.0 {
+			return Poll::Ready(());
+		}
+
+		self.0 = true;
+		cx.waker().wake_by_ref();
+		Poll::Pending
+	}
+}
+
+fn directory(name: &str, files: usize) -> PathBuf {
+	let directory = std::env::temp_dir().join(format!("file_processor_async_{}_{}", name, std::process::id()));
+	std::fs::create_dir_all(&directory).unwrap();
+
+	for i in 0..files {
+		std::fs::write(directory.join(format!("{}.txt", i)), i.to_string()).unwrap();
+	}
+
+	directory
+}
+
+
+#[test]
+fn run_async_awaits_every_entry() {
+	// More files than the walk is allowed to get ahead by
+	let directory = directory("all", 150);
+	let mut found = Vec::new();
+
+	let processed = block_on(FindOptions::new(&directory).run_async(|path| {
+		found.push(path);
+		YieldOnce(false)
+	}));
+	std::fs::remove_dir_all(&directory).unwrap();
+
+	assert_eq!(processed.unwrap(), 150);
+	found.sort();
+	found.dedup();
+	assert_eq!(found.len(), 150);
+}
+
+#[test]
+fn run_async_stops_early() {
+	let directory = directory("stop", 100);
+	let mut seen = 0;
+
+	let processed = block_on(FindOptions::new(&directory).run_async(|_| {
+		seen += 1;
+		let more = seen < 3;
+		async move { more }
+	}));
+	std::fs::remove_dir_all(&directory).unwrap();
+
+	assert_eq!(processed.unwrap(), 3);
+	assert_eq!(seen, 3);
+}
+
+#[test]
+fn find_and_then_async_reports_missing_files() {
+	let directory = directory("missing", 2);
+
+	let result = block_on(file_processor::find_and_then_async(&directory, vec![String::from("0.txt"), String::from("none.txt")], |_| async {}, false, false));
+	std::fs::remove_dir_all(&directory).unwrap();
+
+	assert_eq!(result.unwrap_err(), Error::MissingFiles(vec![String::from("none.txt")]));
+}
+
+#[test]
+fn run_async_honours_the_cancellation_token() {
+	let directory = directory("cancelled", 2);
+	let token = CancellationToken::new();
+	token.cancel();
+
+	let result = block_on(FindOptions::new(&directory).cancellation(token).run_async(|_| async {}));
+	std::fs::remove_dir_all(&directory).unwrap();
+
+	assert_eq!(result.unwrap_err(), Error::Cancelled);
+}
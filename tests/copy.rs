@@ -0,0 +1,89 @@
+use file_processor::{Error, FindOptions};
+
+use std::path::{Path, PathBuf};
+
+
+
+/// A directory removed when dropped, even when an assertion failed
+struct TempDir(PathBuf);
+
+impl TempDir {
+	fn new(name: &str) -> TempDir {
+		let path = std::env::temp_dir().join(format!("file_processor_copy_{}_{}", name, std::process::id()));
+		let _ = std::fs::remove_dir_all(&path);
+		std::fs::create_dir_all(&path).unwrap();
+		TempDir(path)
+	}
+
+	/// Create the file at `relative`, along with its parent directories, holding its own path
+	fn file(&self, relative: &str) -> PathBuf {
+		let path = self.0.join(relative);
+		std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+		std::fs::write(&path, relative).unwrap();
+		path
+	}
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_dir_all(&self.0);
+	}
+}
+
+fn contents(path: &Path) -> String {
+	std::fs::read_to_string(path).unwrap()
+}
+
+
+#[test]
+fn files_are_copied_into_a_new_destination() {
+	let dir = TempDir::new("flat");
+	dir.file("source/a.txt");
+	dir.file("source/b.txt");
+	let destination = dir.0.join("out").join("nested");
+
+	let copied = file_processor::find_and_copy(dir.0.join("source"), vec![String::from("a.txt")], &destination, false).unwrap();
+
+	assert_eq!(copied, 1);
+	assert_eq!(contents(&destination.join("a.txt")), "source/a.txt");
+	assert!(!destination.join("b.txt").exists());
+	// The original is left in place
+	assert!(dir.0.join("source").join("a.txt").exists());
+}
+
+#[test]
+fn missing_files_are_reported() {
+	let dir = TempDir::new("missing");
+	dir.file("source/a.txt");
+
+	let result = file_processor::find_and_copy(dir.0.join("source"), vec![String::from("a.txt"), String::from("none.txt")], dir.0.join("out"), false);
+
+	assert_eq!(result.unwrap_err(), Error::MissingFiles(vec![String::from("none.txt")]));
+}
+
+#[test]
+fn subpaths_are_preserved_on_request() {
+	let dir = TempDir::new("subpaths");
+	dir.file("source/a.txt");
+	dir.file("source/sub/a.txt");
+	let options = FindOptions::new(dir.0.join("source")).recursive(true);
+
+	assert_eq!(options.copy_into(dir.0.join("kept"), true).unwrap(), 2);
+	assert_eq!(contents(&dir.0.join("kept").join("a.txt")), "source/a.txt");
+	assert_eq!(contents(&dir.0.join("kept").join("sub").join("a.txt")), "source/sub/a.txt");
+
+	// Flattened, files sharing a name overwrite each other
+	assert_eq!(options.copy_into(dir.0.join("flat"), false).unwrap(), 2);
+	assert!(!dir.0.join("flat").join("sub").exists());
+	assert!(dir.0.join("flat").join("a.txt").exists());
+}
+
+#[test]
+fn a_destination_inside_the_directory_is_not_searched_again() {
+	let dir = TempDir::new("inside");
+	dir.file("a.txt");
+
+	assert_eq!(FindOptions::new(&dir.0).recursive(true).copy_into(dir.0.join("out"), true).unwrap(), 1);
+	assert!(dir.0.join("out").join("a.txt").exists());
+	assert!(!dir.0.join("out").join("out").exists());
+}
@@ -0,0 +1,85 @@
+use file_processor::{Error, FindOptions};
+
+use std::path::PathBuf;
+
+
+
+/// A directory removed when dropped, even when an assertion failed
+struct TempDir(PathBuf);
+
+impl TempDir {
+	fn new(name: &str) -> TempDir {
+		let path = std::env::temp_dir().join(format!("file_processor_delete_{}_{}", name, std::process::id()));
+		let _ = std::fs::remove_dir_all(&path);
+		std::fs::create_dir_all(&path).unwrap();
+		TempDir(path)
+	}
+
+	/// Create the file at `relative`, along with its parent directories
+	fn file(&self, relative: &str) -> PathBuf {
+		let path = self.0.join(relative);
+		std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+		std::fs::write(&path, relative).unwrap();
+		path
+	}
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_dir_all(&self.0);
+	}
+}
+
+
+#[test]
+fn requested_files_are_deleted() {
+	let dir = TempDir::new("files");
+	let a = dir.file("a.txt");
+	let b = dir.file("b.txt");
+
+	assert_eq!(file_processor::find_and_delete(&dir.0, vec![String::from("a.txt")], false).unwrap(), [dir.0.join("a.txt")]);
+	assert!(!a.exists());
+	assert!(b.exists());
+}
+
+#[test]
+fn directories_are_never_deleted() {
+	let dir = TempDir::new("directories");
+	let file = dir.file("sub/a.txt");
+
+	let result = file_processor::find_and_delete(&dir.0, vec![String::from("sub")], false);
+
+	// A directory is not a match, so it is missing
+	assert_eq!(result.unwrap_err(), Error::MissingFiles(vec![String::from("sub")]));
+	assert!(file.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_are_deleted_instead_of_their_target() {
+	let dir = TempDir::new("symlinks");
+	let target = dir.file("target/a.txt");
+	let link = dir.0.join("link.txt");
+	std::os::unix::fs::symlink(&target, &link).unwrap();
+
+	assert_eq!(file_processor::find_and_delete(&dir.0, vec![String::from("link.txt")], false).unwrap(), [dir.0.join("link.txt")]);
+	assert!(std::fs::symlink_metadata(&link).is_err());
+	assert!(target.exists());
+}
+
+#[test]
+fn delete_files_searches_recursively_and_reports_the_deleted_files() {
+	let dir = TempDir::new("recursive");
+	let a = dir.file("a.tmp");
+	let b = dir.file("sub/b.tmp");
+	let kept = dir.file("sub/c.txt");
+
+	let report = FindOptions::new(&dir.0).extensions(vec![String::from("tmp")]).recursive(true).delete_files().unwrap();
+	let mut deleted = report.processed.clone();
+	deleted.sort();
+
+	assert!(report.is_success());
+	assert_eq!(deleted, [a.clone(), b.clone()]);
+	assert!(!a.exists() && !b.exists());
+	assert!(kept.exists());
+}
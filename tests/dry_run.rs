@@ -0,0 +1,70 @@
+use file_processor::FindOptions;
+
+use std::path::PathBuf;
+
+
+
+/// A directory removed when dropped, even when an assertion failed
+struct TempDir(PathBuf);
+
+impl TempDir {
+	fn new(name: &str) -> TempDir {
+		let path = std::env::temp_dir().join(format!("file_processor_dry_run_{}_{}", name, std::process::id()));
+		let _ = std::fs::remove_dir_all(&path);
+		std::fs::create_dir_all(&path).unwrap();
+
+		for name in ["a.tmp", "b.tmp", "c.txt"] {
+			std::fs::write(path.join(name), name).unwrap();
+		}
+
+		TempDir(path)
+	}
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_dir_all(&self.0);
+	}
+}
+
+
+#[test]
+fn process_is_not_called() {
+	let dir = TempDir::new("process");
+	let options = FindOptions::new(&dir.0).extensions(vec![String::from("tmp")]).dry_run(true);
+
+	assert_eq!(options.run_with::<()>(|_| panic!("processed during a dry run")).unwrap(), 2);
+	assert_eq!(options.run_with_metadata::<()>(|_, _| panic!("processed during a dry run")).unwrap(), 2);
+
+	let report = options.run_collecting_errors(|_| -> Result<(), file_processor::Error> { panic!("processed during a dry run") }).unwrap();
+	assert!(report.processed.is_empty());
+	assert_eq!(report.skipped, 1);
+}
+
+#[test]
+fn plan_lists_the_selection() {
+	let dir = TempDir::new("plan");
+
+	for dry_run in [false, true] {
+		let mut planned = FindOptions::new(&dir.0).extensions(vec![String::from("tmp")]).dry_run(dry_run).plan().unwrap();
+		planned.sort();
+		assert_eq!(planned, [dir.0.join("a.tmp"), dir.0.join("b.tmp")]);
+	}
+}
+
+#[test]
+fn files_are_left_alone() {
+	let dir = TempDir::new("delete");
+	let options = FindOptions::new(&dir.0).extensions(vec![String::from("tmp")]).dry_run(true);
+
+	let mut deleted = options.delete_files().unwrap().processed;
+	deleted.sort();
+	assert_eq!(deleted, [dir.0.join("a.tmp"), dir.0.join("b.tmp")]);
+
+	let copies = dir.0.join("copies");
+	assert_eq!(options.copy_into(&copies, false).unwrap(), 2);
+
+	assert!(dir.0.join("a.tmp").exists());
+	assert!(dir.0.join("b.tmp").exists());
+	assert!(!copies.exists());
+}
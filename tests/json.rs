@@ -0,0 +1,99 @@
+#![cfg(feature = "json")]
+
+use file_processor::{Error, FileModify, SaveFileFormat};
+
+use std::time::{Duration, UNIX_EPOCH};
+
+
+
+fn records() -> Vec<FileModify> {
+	vec![
+		FileModify::new("assets/a.png".into(), UNIX_EPOCH + Duration::new(1_546_300_800, 0)),
+		FileModify::new("assets/sub/b \"quoted\"\\\n\t.txt".into(), UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789)),
+		FileModify::new("données/é 😀.toml".into(), UNIX_EPOCH),
+	]
+}
+
+fn is_invalid(result: Result<Vec<FileModify>, Error>) -> bool {
+	matches!(result, Err(Error::InvalidSnapshot(_)))
+}
+
+
+#[test]
+fn snapshot_round_trips_through_json() {
+	let records = records();
+	let path = std::env::temp_dir().join(format!("file_processor_json_{}.json", std::process::id()));
+
+	file_processor::save_records(&records, &path, SaveFileFormat::JSON).unwrap();
+	let loaded = file_processor::load_records(&path, SaveFileFormat::JSON);
+	std::fs::remove_file(&path).unwrap();
+
+	assert_eq!(loaded.unwrap(), records);
+}
+
+#[test]
+fn manifest_round_trips() {
+	let records = records();
+
+	assert_eq!(file_processor::from_json_manifest(&file_processor::to_json_manifest(&records).unwrap()).unwrap(), records);
+	assert_eq!(file_processor::from_json_manifest("[]").unwrap(), Vec::new());
+}
+
+#[test]
+fn json_lines_round_trip() {
+	let records = records();
+	let mut lines = Vec::new();
+
+	file_processor::write_jsonl_manifest(&records, &mut lines).unwrap();
+	// Appended later, after an empty line
+	lines.push(b'\n');
+	file_processor::write_jsonl_manifest(&records[..1], &mut lines).unwrap();
+
+	let loaded = file_processor::read_jsonl_manifest(&lines[..]).unwrap();
+	assert_eq!(loaded[..3], records[..]);
+	assert_eq!(loaded[3], records[0]);
+}
+
+#[test]
+fn unknown_fields_are_ignored() {
+	let manifest = r#"[{"filename":"a","extra":[-1.5,2e10,0.25E-3,18446744073709551616,true,null,{"x":"y"}],
+		"date":{"secs_since_epoch":1,"nanos_since_epoch":2,"zone":-0}}]"#;
+
+	let loaded = file_processor::from_json_manifest(manifest).unwrap();
+	assert_eq!(loaded, vec![FileModify::new("a".into(), UNIX_EPOCH + Duration::new(1, 2))]);
+}
+
+#[test]
+fn malformed_manifests_are_rejected() {
+	let valid = r#"[{"filename":"a","date":{"secs_since_epoch":1,"nanos_since_epoch":2}}]"#;
+
+	// Every truncation of a valid manifest
+	for end in 0..valid.len() {
+		assert!(is_invalid(file_processor::from_json_manifest(&valid[..end])), "{}", &valid[..end]);
+	}
+
+	for manifest in [
+		"{}",
+		"[1]",
+		r#"[{"filename":1,"date":{"secs_since_epoch":1,"nanos_since_epoch":2}}]"#,
+		r#"[{"filename":"a","date":{"secs_since_epoch":-1,"nanos_since_epoch":2}}]"#,
+		r#"[{"filename":"a","date":{"secs_since_epoch":1.5,"nanos_since_epoch":2}}]"#,
+		r#"[{"filename":"a","date":{"secs_since_epoch":1,"nanos_since_epoch":4294967296}}]"#,
+		r#"[{"filename":"a","date":{"secs_since_epoch":1,"nanos_since_epoch":2},"hash":3}]"#,
+		r#"[{"filename":"a","extra":01,"date":{"secs_since_epoch":1,"nanos_since_epoch":2}}]"#,
+		r#"[{"filename":"a","extra":1.,"date":{"secs_since_epoch":1,"nanos_since_epoch":2}}]"#,
+		r#"[{"filename":"\ud800","date":{"secs_since_epoch":1,"nanos_since_epoch":2}}]"#,
+		"[] []",
+	] {
+		assert!(is_invalid(file_processor::from_json_manifest(manifest)), "{}", manifest);
+	}
+}
+
+#[test]
+fn deep_nesting_is_rejected_without_overflowing_the_stack() {
+	assert!(is_invalid(file_processor::from_json_manifest(&"[".repeat(200_000))));
+	assert!(is_invalid(file_processor::from_json_manifest(&"{\"a\":".repeat(200_000))));
+
+	let nested = format!(r#"[{{"filename":"a","extra":{}{},"date":{{"secs_since_epoch":1,"nanos_since_epoch":2}}}}]"#, "[".repeat(100), "]".repeat(100));
+	assert!(is_invalid(file_processor::from_json_manifest(&nested)));
+}
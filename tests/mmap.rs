@@ -0,0 +1,46 @@
+#![cfg(all(unix, feature = "memmap"))]
+
+use std::path::PathBuf;
+
+
+
+/// A directory removed when dropped, even when an assertion failed
+struct TempDir(PathBuf);
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_dir_all(&self.0);
+	}
+}
+
+
+#[test]
+fn empty_and_non_empty_files_are_mapped() {
+	let dir = TempDir(std::env::temp_dir().join(format!("file_processor_mmap_{}", std::process::id())));
+	std::fs::create_dir_all(&dir.0).unwrap();
+
+	let contents: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+	std::fs::write(dir.0.join("empty.bin"), b"").unwrap();
+	std::fs::write(dir.0.join("data.bin"), &contents).unwrap();
+
+	// SAFETY: nothing writes to the files while they are mapped
+	let mut maps = unsafe { file_processor::find_and_mmap(&dir.0, vec![String::from("empty.bin"), String::from("data.bin")], false) }.unwrap();
+	maps.sort_by(|a, b| a.0.cmp(&b.0));
+
+	assert_eq!(maps.len(), 2);
+	assert_eq!(maps[0].0, dir.0.join("data.bin"));
+	assert_eq!(&maps[0].1[..], &contents[..]);
+	assert_eq!(maps[1].0, dir.0.join("empty.bin"));
+	assert!(maps[1].1.is_empty());
+	assert_eq!(maps[1].1.as_ref(), b"");
+}
+
+#[test]
+fn missing_files_are_reported() {
+	let dir = TempDir(std::env::temp_dir().join(format!("file_processor_mmap_missing_{}", std::process::id())));
+	std::fs::create_dir_all(&dir.0).unwrap();
+
+	// SAFETY: no file is mapped
+	let result = unsafe { file_processor::find_and_mmap(&dir.0, vec![String::from("none.bin")], false) };
+	assert_eq!(result.unwrap_err(), file_processor::Error::MissingFiles(vec![String::from("none.bin")]));
+}
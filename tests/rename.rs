@@ -0,0 +1,90 @@
+use file_processor::Error;
+
+use std::path::{Path, PathBuf};
+
+
+
+/// A directory removed when dropped, even when an assertion failed
+struct TempDir(PathBuf);
+
+impl TempDir {
+	fn new(name: &str) -> TempDir {
+		let path = std::env::temp_dir().join(format!("file_processor_rename_{}_{}", name, std::process::id()));
+		let _ = std::fs::remove_dir_all(&path);
+		std::fs::create_dir_all(&path).unwrap();
+		TempDir(path)
+	}
+
+	fn file(&self, name: &str) -> PathBuf {
+		let path = self.0.join(name);
+		std::fs::write(&path, name).unwrap();
+		path
+	}
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_dir_all(&self.0);
+	}
+}
+
+fn contents(path: &Path) -> String {
+	std::fs::read_to_string(path).unwrap()
+}
+
+
+#[test]
+fn files_are_renamed() {
+	let dir = TempDir::new("order");
+	let a = dir.file("a.txt");
+	let b = dir.file("b.txt");
+
+	let mut renamed = file_processor::find_and_rename(&dir.0, vec![String::from("b.txt"), String::from("a.txt")], |path| path.with_extension("md"), false).unwrap();
+
+	// In the order of the directory listing
+	renamed.sort();
+	assert_eq!(renamed, vec![(a.clone(), a.with_extension("md")), (b.clone(), b.with_extension("md"))]);
+	assert!(!a.exists() && !b.exists());
+	assert_eq!(contents(&a.with_extension("md")), "a.txt");
+}
+
+#[test]
+fn existing_targets_are_never_overwritten() {
+	let dir = TempDir::new("existing");
+	let a = dir.file("a.txt");
+	let b = dir.file("b.txt");
+
+	let result = file_processor::find_and_rename(&dir.0, vec![String::from("a.txt")], |path| path.with_file_name("b.txt"), false);
+	match result {
+		Err(Error::IoError(err)) => assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists),
+		other => panic!("expected AlreadyExists, got {:?}", other),
+	}
+	assert_eq!(contents(&a), "a.txt");
+	assert_eq!(contents(&b), "b.txt");
+
+	// Renaming a file to itself is refused too, and skipped with `ignore_fail`
+	let renamed = file_processor::find_and_rename(&dir.0, vec![String::from("a.txt")], |path| path.to_path_buf(), true).unwrap();
+	assert!(renamed.is_empty());
+	assert_eq!(contents(&a), "a.txt");
+}
+
+#[test]
+fn files_are_moved_across_filesystems() {
+	let dir = TempDir::new("devices");
+	let a = dir.file("a.txt");
+
+	// A tmpfs is a filesystem of its own, where no hard link to the temporary directory can be made
+	let shm = Path::new("/dev/shm");
+	if !shm.is_dir() {
+		return;
+	}
+	let target = shm.join(format!("file_processor_rename_{}.txt", std::process::id()));
+
+	let renamed = file_processor::find_and_rename(&dir.0, vec![String::from("a.txt")], |_| target.clone(), false);
+	let moved = std::fs::read_to_string(&target);
+	let _ = std::fs::remove_file(&target);
+
+	assert_eq!(renamed.unwrap(), vec![(a.clone(), target)]);
+	assert_eq!(moved.unwrap(), "a.txt");
+	assert!(!a.exists());
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "ron")]
+
+use file_processor::{Error, FileModify, SaveFileFormat};
+
+use std::time::{Duration, UNIX_EPOCH};
+
+
+
+/// Load a snapshot saved as `text`
+fn load(name: &str, text: &str) -> Result<Vec<FileModify>, Error> {
+	let path = std::env::temp_dir().join(format!("file_processor_ron_{}_{}.ron", name, std::process::id()));
+
+	std::fs::write(&path, text).unwrap();
+	let loaded = file_processor::load_records(&path, SaveFileFormat::RON);
+	std::fs::remove_file(&path).unwrap();
+
+	loaded
+}
+
+fn is_invalid(result: Result<Vec<FileModify>, Error>) -> bool {
+	matches!(result, Err(Error::InvalidSnapshot(_)))
+}
+
+
+#[test]
+fn snapshot_round_trips_through_ron() {
+	let records = vec![
+		FileModify::new("assets/a.png".into(), UNIX_EPOCH + Duration::new(1_546_300_800, 0)),
+		FileModify::new("assets/sub/b \"quoted\"\\\n.txt".into(), UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789)),
+		FileModify::new("données/é.toml".into(), UNIX_EPOCH),
+	];
+
+	let path = std::env::temp_dir().join(format!("file_processor_ron_{}.ron", std::process::id()));
+
+	file_processor::save_records(&records, &path, SaveFileFormat::RON).unwrap();
+	let loaded = file_processor::load_records(&path, SaveFileFormat::RON);
+	std::fs::remove_file(&path).unwrap();
+
+	assert_eq!(loaded.unwrap(), records);
+}
+
+#[test]
+fn struct_names_trailing_commas_and_comments_are_accepted() {
+	let text = "// snapshot\n[\n\tFileModify(filename: \"a\", /* unix */ date: (secs_since_epoch: 1, nanos_since_epoch: 2,),),\n]";
+
+	assert_eq!(load("lenient", text).unwrap(), vec![FileModify::new("a".into(), UNIX_EPOCH + Duration::new(1, 2))]);
+}
+
+#[test]
+fn malformed_snapshots_are_rejected() {
+	let valid = "[(filename:\"a\",date:(secs_since_epoch:1,nanos_since_epoch:2))]";
+
+	// Every truncation of a valid snapshot
+	for end in 0..valid.len() {
+		assert!(is_invalid(load("truncated", &valid[..end])), "{}", &valid[..end]);
+	}
+
+	for text in [
+		"(filename:\"a\",date:(secs_since_epoch:1,nanos_since_epoch:2))",
+		"[1]",
+		"[(filename:1,date:(secs_since_epoch:1,nanos_since_epoch:2))]",
+		"[(filename:\"a\",date:(secs_since_epoch:1,nanos_since_epoch:4294967296))]",
+		"[(filename:\"a\",date:(secs_since_epoch:99999999999999999999,nanos_since_epoch:2))]",
+		"[(filename:\"a\",date:(secs_since_epoch:1,nanos_since_epoch:2),hash:3)]",
+		"[(filename:\"\\q\",date:(secs_since_epoch:1,nanos_since_epoch:2))]",
+		"[(filename:\"a\" date:(secs_since_epoch:1,nanos_since_epoch:2))]",
+		"[] /* unterminated",
+		"[] []",
+	] {
+		assert!(is_invalid(load("malformed", text)), "{}", text);
+	}
+}
+
+#[test]
+fn deep_nesting_is_rejected_without_overflowing_the_stack() {
+	assert!(is_invalid(load("seq", &"[".repeat(200_000))));
+	assert!(is_invalid(load("struct", &"(a:".repeat(200_000))));
+	assert!(is_invalid(load("named", &"A(a:".repeat(200_000))));
+}
@@ -0,0 +1,335 @@
+use file_processor::{CancellationToken, EntryKind, Error, FindOptions, SortOrder, SymlinkPolicy};
+
+use std::path::{Path, PathBuf};
+
+
+
+/// A directory removed when dropped, even when an assertion failed
+struct TempDir(PathBuf);
+
+impl TempDir {
+	fn new(name: &str) -> TempDir {
+		let path = std::env::temp_dir().join(format!("file_processor_search_{}_{}", name, std::process::id()));
+		let _ = std::fs::remove_dir_all(&path);
+		std::fs::create_dir_all(&path).unwrap();
+		TempDir(path)
+	}
+
+	/// Create the file at `relative`, along with its parent directories, holding its own path
+	fn file(&self, relative: &str) -> PathBuf {
+		let path = self.0.join(relative);
+		std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+		std::fs::write(&path, relative).unwrap();
+		path
+	}
+
+	/// The files `options` selects, relative to the directory with `/` separators, sorted
+	fn found(&self, options: FindOptions) -> Vec<String> {
+		let mut found = Vec::new();
+
+		options.entry_kind(EntryKind::FilesOnly).run_with(|path| found.push(relative(&self.0, path))).unwrap();
+
+		found.sort();
+		found
+	}
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_dir_all(&self.0);
+	}
+}
+
+fn relative(directory: &Path, path: &Path) -> String {
+	let relative = path.strip_prefix(directory).unwrap();
+	relative.iter().map(|c| c.to_str().unwrap()).collect::<Vec<_>>().join("/")
+}
+
+
+#[test]
+fn subdirectories_are_only_searched_when_recursive() {
+	let dir = TempDir::new("recursive");
+	dir.file("a.txt");
+	dir.file("sub/b.txt");
+	dir.file("sub/deeper/c.txt");
+
+	assert_eq!(dir.found(FindOptions::new(&dir.0)), ["a.txt"]);
+	assert_eq!(dir.found(FindOptions::new(&dir.0).recursive(true)), ["a.txt", "sub/b.txt", "sub/deeper/c.txt"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_pointing_back_up_the_tree_are_walked_once() {
+	let dir = TempDir::new("loop");
+	dir.file("sub/a.txt");
+	std::os::unix::fs::symlink(&dir.0, dir.0.join("sub").join("up")).unwrap();
+
+	assert_eq!(dir.found(FindOptions::new(&dir.0).recursive(true)), ["sub/a.txt"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_files_have_the_metadata_of_their_target() {
+	let dir = TempDir::new("metadata");
+	let target = dir.file("target.txt");
+	std::os::unix::fs::symlink(&target, dir.0.join("link.txt")).unwrap();
+	std::fs::create_dir(dir.0.join("sub")).unwrap();
+
+	let mut sizes = Vec::new();
+	FindOptions::new(&dir.0).run_with_metadata(|path, metadata| {
+		let len = if metadata.is_dir() { None } else { Some(metadata.len()) };
+		sizes.push((relative(&dir.0, path), len));
+	}).unwrap();
+	sizes.sort();
+
+	let len = Some("target.txt".len() as u64);
+	assert_eq!(sizes, [(String::from("link.txt"), len), (String::from("sub"), None), (String::from("target.txt"), len)]);
+}
+
+#[test]
+fn filenames_can_be_matched_ignoring_case() {
+	let dir = TempDir::new("case");
+	dir.file("readme.md");
+	let requested = vec![String::from("README.md")];
+
+	let result = FindOptions::new(&dir.0).filenames(requested.clone()).run_with(|_| ());
+	assert_eq!(result.unwrap_err(), Error::MissingFiles(requested.clone()));
+
+	assert_eq!(dir.found(FindOptions::new(&dir.0).filenames(requested).case_insensitive(true)), ["readme.md"]);
+}
+
+#[test]
+fn duplicate_filenames_are_looked_up_once() {
+	let dir = TempDir::new("duplicates");
+	dir.file("a.txt");
+	let requested = vec![String::from("a.txt"), String::from("b.txt"), String::from("a.txt"), String::from("b.txt")];
+
+	let mut processed = Vec::new();
+	let result = file_processor::find_and_then(&dir.0, requested, |path| processed.push(path.clone()), false, false);
+
+	assert_eq!(result.unwrap_err(), Error::MissingFiles(vec![String::from("b.txt")]));
+	assert_eq!(processed, [dir.0.join("a.txt")]);
+
+	// Names only differing in case are the same name once the case is ignored
+	let requested = vec![String::from("A.txt"), String::from("a.TXT")];
+	assert_eq!(dir.found(FindOptions::new(&dir.0).filenames(requested).case_insensitive(true)), ["a.txt"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_are_followed_skipped_or_rejected() {
+	let dir = TempDir::new("symlinks");
+	let target = dir.file("target/a.txt");
+	std::os::unix::fs::symlink(&target, dir.0.join("file_link.txt")).unwrap();
+	std::os::unix::fs::symlink(dir.0.join("target"), dir.0.join("dir_link")).unwrap();
+
+	let followed = dir.found(FindOptions::new(&dir.0).recursive(true).symlinks(SymlinkPolicy::Follow));
+	// The target directory is walked once, through whichever of its paths comes first
+	assert!(followed.contains(&String::from("file_link.txt")));
+	assert_eq!(followed.len(), 2);
+
+	assert_eq!(dir.found(FindOptions::new(&dir.0).recursive(true).symlinks(SymlinkPolicy::Skip)), ["target/a.txt"]);
+
+	let result = FindOptions::new(&dir.0).recursive(true).symlinks(SymlinkPolicy::Error).sort(Some(SortOrder::NameAsc)).run_with(|_| ());
+	assert_eq!(result.unwrap_err(), Error::UnexpectedSymlink(dir.0.join("dir_link")));
+}
+
+#[test]
+fn process_can_stop_the_search_early() {
+	let dir = TempDir::new("stop");
+	for name in ["a.txt", "b.txt", "c.txt"] {
+		dir.file(name);
+	}
+
+	let mut processed = 0;
+	let count = FindOptions::new(&dir.0).run_with(|_| {
+		processed += 1;
+		processed < 2
+	}).unwrap();
+	assert_eq!((count, processed), (2, 2));
+
+	// A search stopped early does not report the files it did not get to as missing
+	let requested = vec![String::from("a.txt"), String::from("b.txt"), String::from("missing.txt")];
+	let count = FindOptions::new(&dir.0).filenames(requested).run_with(|_| std::ops::ControlFlow::Break(())).unwrap();
+	assert_eq!(count, 1);
+}
+
+#[test]
+fn failures_are_collected_with_their_path() {
+	let dir = TempDir::new("collecting");
+	for name in ["a.txt", "b.txt", "c.txt"] {
+		dir.file(name);
+	}
+
+	let report = FindOptions::new(&dir.0).sort(Some(SortOrder::NameAsc)).run_collecting_errors(|path| {
+		if path.ends_with("b.txt") {
+			return Err(Error::InvalidUnicodeData);
+		}
+
+		Ok(relative(&dir.0, path))
+	}).unwrap();
+
+	assert_eq!(report.processed, ["a.txt", "c.txt"]);
+	assert_eq!(report.errors, [(dir.0.join("b.txt"), Error::InvalidUnicodeData)]);
+	assert!(!report.is_success());
+}
+
+#[cfg(unix)]
+#[test]
+fn unreadable_metadata_fails_unless_ignored() {
+	let dir = TempDir::new("ignore_fail");
+	dir.file("a.txt");
+	std::os::unix::fs::symlink(dir.0.join("missing"), dir.0.join("broken.txt")).unwrap();
+	let options = FindOptions::new(&dir.0).size(Some(1), None);
+
+	assert!(matches!(options.run_with(|_| ()), Err(Error::IoError(_))));
+	assert_eq!(dir.found(options.clone().ignore_fail(true)), ["a.txt"]);
+
+	let report = options.run_collecting_errors(|path| Ok(path.clone())).unwrap();
+	assert_eq!(report.processed, [dir.0.join("a.txt")]);
+	assert_eq!(report.errors.len(), 1);
+	assert_eq!(report.errors[0].0, dir.0.join("broken.txt"));
+}
+
+#[test]
+fn max_depth_limits_the_levels_walked() {
+	let dir = TempDir::new("max_depth");
+	dir.file("a.txt");
+	dir.file("one/b.txt");
+	dir.file("one/two/c.txt");
+	let options = FindOptions::new(&dir.0).recursive(true);
+
+	assert_eq!(dir.found(options.clone().max_depth(Some(0))), ["a.txt"]);
+	assert_eq!(dir.found(options.clone().max_depth(Some(1))), ["a.txt", "one/b.txt"]);
+	assert_eq!(dir.found(options.clone().max_depth(Some(2))), ["a.txt", "one/b.txt", "one/two/c.txt"]);
+	assert_eq!(dir.found(options.max_depth(None)), ["a.txt", "one/b.txt", "one/two/c.txt"]);
+}
+
+#[test]
+fn cancelled_searches_stop_before_the_next_entry() {
+	let dir = TempDir::new("cancel");
+	for name in ["a.txt", "b.txt", "c.txt"] {
+		dir.file(name);
+	}
+
+	let token = CancellationToken::new();
+	let options = FindOptions::new(&dir.0).cancellation(token.clone()).ignore_fail(true);
+
+	let mut processed = 0;
+	let result = options.run_with(|_| {
+		processed += 1;
+		token.cancel();
+	});
+	assert_eq!(result.unwrap_err(), Error::Cancelled);
+	assert_eq!(processed, 1);
+
+	// Still cancelled, so nothing is processed
+	assert_eq!(options.run_with::<()>(|_| panic!("processed after cancelling")).unwrap_err(), Error::Cancelled);
+}
+
+#[test]
+fn searches_running_past_their_timeout_stop() {
+	let dir = TempDir::new("timeout");
+	dir.file("a.txt");
+
+	let expired = FindOptions::new(&dir.0).timeout(Some(std::time::Duration::ZERO)).ignore_fail(true).run_with(|_| ());
+	assert_eq!(expired.unwrap_err(), Error::TimedOut);
+
+	let unlimited = FindOptions::new(&dir.0).timeout(Some(std::time::Duration::MAX)).run_with(|_| ());
+	assert_eq!(unlimited.unwrap(), 1);
+}
+
+/// The paths `options` hands to `process`, sorted
+fn paths(options: FindOptions) -> Vec<PathBuf> {
+	let mut paths = Vec::new();
+	options.entry_kind(EntryKind::FilesOnly).run_with(|path| paths.push(path.clone())).unwrap();
+	paths.sort();
+	paths
+}
+
+#[test]
+fn paths_can_be_made_canonical() {
+	let dir = TempDir::new("canonicalize");
+	dir.file("sub/a.txt");
+	let indirect = dir.0.join("sub").join("..");
+	let canonical = dir.0.canonicalize().unwrap();
+
+	assert_eq!(paths(FindOptions::new(&indirect).recursive(true)), [indirect.join("sub").join("a.txt")]);
+	assert_eq!(paths(FindOptions::new(&indirect).recursive(true).canonicalize(true)), [canonical.join("sub").join("a.txt")]);
+}
+
+#[cfg(unix)]
+#[test]
+fn canonical_paths_resolve_symlinks() {
+	let dir = TempDir::new("canonical_links");
+	dir.file("target/a.txt");
+	std::os::unix::fs::symlink(dir.0.join("target").join("a.txt"), dir.0.join("link.txt")).unwrap();
+	let target = dir.0.canonicalize().unwrap().join("target").join("a.txt");
+
+	// Both entries are processed, with the same path
+	assert_eq!(paths(FindOptions::new(&dir.0).recursive(true).canonicalize(true)), [target.clone(), target]);
+}
+
+#[test]
+fn paths_can_be_made_relative() {
+	let dir = TempDir::new("relative");
+	dir.file("a.txt");
+	dir.file("sub/b.txt");
+	let options = FindOptions::new(&dir.0).recursive(true).relative(true);
+
+	assert_eq!(paths(options.clone()), [PathBuf::from("a.txt"), Path::new("sub").join("b.txt")]);
+	// Relative to the canonical directory, whatever path it was given by
+	assert_eq!(paths(FindOptions::new(dir.0.join("sub").join("..")).recursive(true).relative(true).canonicalize(true)), paths(options));
+}
+
+#[test]
+fn pooled_results_keep_the_order_files_were_found_in() {
+	let dir = TempDir::new("pooled");
+	let mut requested = Vec::new();
+	for i in 0..20 {
+		let name = format!("{:02}.txt", i);
+		dir.file(&name);
+		requested.push(name);
+	}
+	requested.push(String::from("missing.txt"));
+
+	let workers = std::num::NonZeroUsize::new(4).unwrap();
+	let report = file_processor::find_and_then_pooled(&dir.0, requested, |path| {
+		let name = relative(&dir.0, path);
+		if name == "07.txt" { Err(Error::InvalidUnicodeData) } else { Ok(name) }
+	}, workers, false).unwrap();
+
+	let mut found = Vec::new();
+	FindOptions::new(&dir.0).run_with(|path| {
+		let name = relative(&dir.0, path);
+		if name != "07.txt" {
+			found.push(name);
+		}
+	}).unwrap();
+
+	assert_eq!(report.processed, found);
+	assert_eq!(report.errors, [(dir.0.join("07.txt"), Error::InvalidUnicodeData)]);
+	assert_eq!(report.missing, ["missing.txt"]);
+}
+
+#[test]
+fn fail_fast_stops_once_everything_is_found() {
+	let dir = TempDir::new("fail_fast");
+	dir.file("a.txt");
+	dir.file("sub/b.txt");
+	dir.file("sub/later/a.txt");
+	dir.file("sub/later/b.txt");
+	let options = FindOptions::new(&dir.0).recursive(true).sort(Some(SortOrder::NameAsc)).fail_fast(true);
+
+	// Both names are found before `sub/later` is walked
+	let found = paths(options.clone().filenames(vec![String::from("a.txt"), String::from("b.txt")]));
+	assert_eq!(found, [dir.0.join("a.txt"), dir.0.join("sub").join("b.txt")]);
+
+	// Only the first missing name is reported
+	let requested = vec![String::from("a.txt"), String::from("x.txt"), String::from("y.txt")];
+	let result = options.clone().filenames(requested.clone()).run_with(|_| ());
+	assert_eq!(result.unwrap_err(), Error::MissingFiles(vec![String::from("x.txt")]));
+
+	let result = options.fail_fast(false).filenames(requested).run_with(|_| ());
+	assert_eq!(result.unwrap_err(), Error::MissingFiles(vec![String::from("x.txt"), String::from("y.txt")]));
+}
@@ -0,0 +1,69 @@
+#![cfg(unix)]
+
+use file_processor::{FindOptions, SortOrder};
+
+use std::path::PathBuf;
+use std::process::Command;
+
+
+
+/// A directory removed when dropped, even when an assertion failed
+struct TempDir(PathBuf);
+
+impl TempDir {
+	fn new(name: &str) -> TempDir {
+		let path = std::env::temp_dir().join(format!("file_processor_sort_{}_{}", name, std::process::id()));
+		let _ = std::fs::remove_dir_all(&path);
+		std::fs::create_dir_all(&path).unwrap();
+		TempDir(path)
+	}
+
+	/// Create the file `name`, modified at `stamp` (`[[CC]YY]MMDDhhmm`, the format of `touch -t`)
+	fn file(&self, name: &str, stamp: &str) {
+		let path = self.0.join(name);
+		std::fs::write(&path, name).unwrap();
+		assert!(Command::new("touch").arg("-t").arg(stamp).arg(&path).status().unwrap().success());
+	}
+
+	fn sorted(&self, order: SortOrder) -> Vec<String> {
+		let mut names = Vec::new();
+
+		FindOptions::new(&self.0).sort(Some(order)).run_with(|path| {
+			names.push(path.file_name().unwrap().to_str().unwrap().to_string());
+		}).unwrap();
+
+		names
+	}
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_dir_all(&self.0);
+	}
+}
+
+
+#[test]
+fn entries_are_sorted_by_name() {
+	let dir = TempDir::new("name");
+	for name in ["b", "a", "c", "B"] {
+		dir.file(name, "202001010000");
+	}
+
+	assert_eq!(dir.sorted(SortOrder::NameAsc), ["B", "a", "b", "c"]);
+	assert_eq!(dir.sorted(SortOrder::NameDesc), ["c", "b", "a", "B"]);
+}
+
+#[test]
+fn entries_are_sorted_by_date_then_name() {
+	let dir = TempDir::new("modified");
+	dir.file("new", "202003010000");
+	dir.file("old_b", "202001010000");
+	dir.file("old_a", "202001010000");
+	dir.file("middle", "202002010000");
+	// Its date cannot be read, as it points nowhere
+	std::os::unix::fs::symlink(dir.0.join("missing"), dir.0.join("broken")).unwrap();
+
+	assert_eq!(dir.sorted(SortOrder::ModifiedAsc), ["old_a", "old_b", "middle", "new", "broken"]);
+	assert_eq!(dir.sorted(SortOrder::ModifiedDesc), ["new", "middle", "old_a", "old_b", "broken"]);
+}
@@ -0,0 +1,102 @@
+#![cfg(all(target_os = "linux", feature = "watch"))]
+
+use file_processor::{FindOptions, WatchEvent};
+
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+
+
+
+/// A directory removed when dropped, even when an assertion failed
+struct TempDir(PathBuf);
+
+impl TempDir {
+	fn new(name: &str) -> TempDir {
+		let path = std::env::temp_dir().join(format!("file_processor_watch_{}_{}", name, std::process::id()));
+		let _ = std::fs::remove_dir_all(&path);
+		std::fs::create_dir_all(&path).unwrap();
+		TempDir(path)
+	}
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_dir_all(&self.0);
+	}
+}
+
+fn next(events: &Receiver<(PathBuf, WatchEvent)>) -> (PathBuf, WatchEvent) {
+	events.recv_timeout(Duration::from_secs(5)).expect("no event within 5 seconds")
+}
+
+
+#[test]
+fn watch_events_reports_created_modified_and_removed() {
+	let dir = TempDir::new("events");
+	let directory = dir.0.clone();
+	std::fs::create_dir_all(directory.join("sub")).unwrap();
+
+	let (sender, events) = channel();
+	let watcher = FindOptions::new(&directory).extensions(vec![String::from("txt")]).recursive(true).watch_events(move |path, event| {
+		sender.send((path.clone(), event)).unwrap();
+	}).unwrap();
+
+	let file = directory.join("sub").join("a.txt");
+	std::fs::write(&file, "1").unwrap();
+	// Reported once the write settled, so the next write gets a later modification date
+	assert_eq!(next(&events), (file.clone(), WatchEvent::Created));
+
+	// Not matching the extension
+	std::fs::write(directory.join("b.log"), "").unwrap();
+
+	std::fs::write(&file, "2").unwrap();
+	assert_eq!(next(&events), (file.clone(), WatchEvent::Modified));
+
+	std::fs::remove_file(&file).unwrap();
+	assert_eq!(next(&events), (file, WatchEvent::Removed));
+
+	// A subdirectory created after the watcher started is watched too. It is watched before the search
+	// reporting its first file, so nothing written in it after that report is missed
+	let first = directory.join("new").join("first.txt");
+	let nested = directory.join("new").join("c.txt");
+	std::fs::create_dir(directory.join("new")).unwrap();
+	std::fs::write(&first, "").unwrap();
+	assert_eq!(next(&events), (first, WatchEvent::Created));
+
+	std::fs::write(&nested, "").unwrap();
+	assert_eq!(next(&events), (nested, WatchEvent::Created));
+
+	watcher.stop().unwrap();
+
+	assert!(events.try_recv().is_err());
+}
+
+#[test]
+fn continuous_writes_are_still_reported() {
+	let dir = TempDir::new("continuous");
+	let file = dir.0.join("busy.txt");
+
+	let (sender, events) = channel();
+	let watcher = FindOptions::new(&dir.0).watch_events(move |path, event| {
+		sender.send((path.clone(), event)).unwrap();
+	}).unwrap();
+
+	// Written more often than changes settle, for longer than changes are waited for to settle
+	let writer = {
+		let file = file.clone();
+		std::thread::spawn(move || {
+			for i in 0..300 {
+				std::fs::write(&file, i.to_string()).unwrap();
+				std::thread::sleep(Duration::from_millis(10));
+			}
+		})
+	};
+
+	// Before the writes stop, three seconds in
+	let created = events.recv_timeout(Duration::from_secs(2)).expect("no event while the file was being written");
+	assert_eq!(created, (file, WatchEvent::Created));
+
+	writer.join().unwrap();
+	watcher.stop().unwrap();
+}